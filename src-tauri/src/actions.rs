@@ -1,33 +1,265 @@
-use crate::models::SearchResult;
-use crate::ai::ai_request;
+use crate::models::{ActionType, SearchResult};
+use crate::config::load_config;
+use crate::conversation::{reopen_conversation, start_conversation};
+use crate::desktop_entry::DesktopEntry;
+use crate::history;
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::{Command, Stdio};
 use tauri::{command, Window};
 
+// Tried in order when no terminal emulator is configured, with the argument
+// that introduces the command to run
+const TERMINAL_CANDIDATES: [(&str, &str); 8] = [
+    ("x-terminal-emulator", "-e"),
+    ("gnome-terminal", "--"),
+    ("konsole", "-e"),
+    ("xfce4-terminal", "-x"),
+    ("alacritty", "-e"),
+    ("kitty", "--"),
+    ("foot", "--"),
+    ("xterm", "-e"),
+];
+
+/// Runs the result's primary action. `query` is what was typed to find it and
+/// feeds the launch history used for ranking.
 #[command]
-pub async fn execute_action(result: SearchResult, window: Window) -> Result<String, String> {
+pub async fn execute_action(
+    result: SearchResult,
+    query: Option<String>,
+    window: Window,
+) -> Result<String, String> {
+    let result_id = result.id.clone();
+    let is_launch = is_launch(&result.action_type);
+    let output = run_action(result, window).await?;
+    if is_launch {
+        record_launch(query.as_deref(), &result_id);
+    }
+    Ok(output)
+}
+
+// Only launches say something about what the user looks for; hints, AI
+// prompts and history management are not boosted
+fn is_launch(action_type: &ActionType) -> bool {
+    matches!(
+        action_type,
+        ActionType::OpenApp
+            | ActionType::OpenFile
+            | ActionType::OpenWith
+            | ActionType::DesktopAction
+            | ActionType::OpenUrl
+            | ActionType::RunCommand
+            | ActionType::RunInTerminal
+    )
+}
+
+async fn run_action(result: SearchResult, window: Window) -> Result<String, String> {
     match result.action_type {
-        crate::models::ActionType::OpenFile | crate::models::ActionType::OpenApp => {
+        crate::models::ActionType::OpenFile => {
             open_with_system(&result.action_data)
         }
+        crate::models::ActionType::OpenApp => {
+            launch_application(&result.action_data, None, &[]).await
+        }
+        crate::models::ActionType::DesktopAction => {
+            let (desktop_file, action_id) = result
+                .action_data
+                .rsplit_once('#')
+                .ok_or("Malformed desktop action")?;
+            launch_application(desktop_file, Some(action_id), &[]).await
+        }
+        crate::models::ActionType::OpenWith => {
+            let (desktop_file, file) = result
+                .action_data
+                .split_once('#')
+                .ok_or("Malformed open with action")?;
+            launch_application(desktop_file, None, &[file.to_string()]).await
+        }
         crate::models::ActionType::OpenUrl => {
             open_with_system(&result.action_data)
         }
         crate::models::ActionType::CopyToClipboard => {
             copy_to_clipboard(&result.action_data)
         }
+        crate::models::ActionType::CopyCalculation => {
+            crate::search::calculator::remember_answer(&result.action_data);
+            copy_to_clipboard(&result.action_data)
+        }
         crate::models::ActionType::AiResponse => {
             handle_ai_response(result.action_data, window).await
         }
+        crate::models::ActionType::RunCommand => run_shell_command(&result.action_data, false).await,
+        crate::models::ActionType::RunInTerminal => {
+            run_shell_command(&result.action_data, true).await
+        }
+        crate::models::ActionType::Hint => Ok(String::new()),
+        // Returns the id of the reopened conversation, like an AI response
+        crate::models::ActionType::ReopenConversation => {
+            reopen_conversation(&result.action_data, &window)
+        }
+        crate::models::ActionType::DeleteAiExchange => {
+            let ids = result.action_data.split(',').map(str::to_string).collect();
+            crate::ai_history::delete_ai_exchanges(ids)?;
+            Ok("Deleted".to_string())
+        }
+    }
+}
+
+/// Runs one of the result's secondary actions, such as a desktop action
+/// or opening the containing folder.
+#[command]
+pub async fn execute_secondary_action(
+    result: SearchResult,
+    action_id: String,
+    query: Option<String>,
+    window: Window,
+) -> Result<String, String> {
+    let action = result
+        .secondary_actions
+        .iter()
+        .find(|action| action.id == action_id)
+        .cloned()
+        .ok_or_else(|| format!("Unknown action '{}'", action_id))?;
+    let result_id = result.id.clone();
+    let is_launch = is_launch(&action.action_type);
+
+    let secondary = SearchResult {
+        id: format!("{}_{}", result.id, action.id),
+        title: action.title,
+        action_type: action.action_type,
+        action_data: action.action_data,
+        secondary_actions: Vec::new(),
+        action_label: None,
+        ..result
+    };
+    let output = run_action(secondary, window).await?;
+    if is_launch {
+        record_launch(query.as_deref(), &result_id);
+    }
+    Ok(output)
+}
+
+fn record_launch(query: Option<&str>, result_id: &str) {
+    if let Err(e) = history::record_launch(query.unwrap_or_default(), result_id) {
+        eprintln!("Failed to record launch of {}: {}", result_id, e);
     }
 }
 
 fn open_with_system(path: &str) -> Result<String, String> {
-    std::process::Command::new("xdg-open")
-        .arg(path)
-        .spawn()
-        .map_err(|e| e.to_string())?;
+    let mut command = Command::new("xdg-open");
+    command.arg(path);
+    spawn_detached(command)?;
     Ok("Opened".to_string())
 }
 
+async fn launch_application(
+    desktop_file: &str,
+    action_id: Option<&str>,
+    files: &[String],
+) -> Result<String, String> {
+    let path = Path::new(desktop_file);
+    let id = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let entry = DesktopEntry::parse(path, id).ok_or("Could not read desktop entry")?;
+
+    let mut arguments = match action_id {
+        Some(action_id) => entry.action_exec_arguments(action_id)?,
+        None => entry.exec_arguments(files)?,
+    };
+    if entry.terminal {
+        let config = load_config().await.unwrap_or_default();
+        let mut terminal = terminal_command(config.terminal_emulator.as_deref())?;
+        terminal.append(&mut arguments);
+        arguments = terminal;
+    }
+
+    let mut command = Command::new(&arguments[0]);
+    command.args(&arguments[1..]);
+
+    let working_dir = entry
+        .working_dir
+        .as_deref()
+        .map(Path::new)
+        .filter(|dir| dir.is_dir())
+        .map(Path::to_path_buf)
+        .or_else(dirs::home_dir);
+    if let Some(working_dir) = working_dir {
+        command.current_dir(working_dir);
+    }
+
+    spawn_detached(command)?;
+    Ok(format!("Launched {}", entry.name))
+}
+
+async fn run_shell_command(shell_command: &str, in_terminal: bool) -> Result<String, String> {
+    let mut arguments = vec!["sh".to_string(), "-c".to_string()];
+    if in_terminal {
+        // Keep the terminal open so the output can be read
+        arguments.push(format!("{}; exec \"${{SHELL:-sh}}\"", shell_command));
+        let config = load_config().await.unwrap_or_default();
+        let mut terminal = terminal_command(config.terminal_emulator.as_deref())?;
+        terminal.append(&mut arguments);
+        arguments = terminal;
+    } else {
+        arguments.push(shell_command.to_string());
+    }
+
+    let mut command = Command::new(&arguments[0]);
+    command.args(&arguments[1..]);
+    if let Some(home) = dirs::home_dir() {
+        command.current_dir(home);
+    }
+
+    spawn_detached(command)?;
+    Ok(format!("Started {}", shell_command))
+}
+
+/// Resolves the terminal command line that the program arguments are appended to.
+fn terminal_command(configured: Option<&str>) -> Result<Vec<String>, String> {
+    if let Some(configured) = configured.filter(|command| !command.trim().is_empty()) {
+        return Ok(configured.split_whitespace().map(str::to_string).collect());
+    }
+
+    if let Ok(terminal) = std::env::var("TERMINAL") {
+        if !terminal.is_empty() && is_on_path(&terminal) {
+            return Ok(vec![terminal, "-e".to_string()]);
+        }
+    }
+
+    TERMINAL_CANDIDATES
+        .iter()
+        .find(|(program, _)| is_on_path(program))
+        .map(|(program, separator)| vec![program.to_string(), separator.to_string()])
+        .ok_or_else(|| "No terminal emulator found; set one in the settings".to_string())
+}
+
+fn is_on_path(program: &str) -> bool {
+    if program.contains('/') {
+        return Path::new(program).is_file();
+    }
+    std::env::var_os("PATH").is_some_and(|path| {
+        std::env::split_paths(&path).any(|dir| dir.join(program).is_file())
+    })
+}
+
+/// Starts a process that outlives the launcher: it gets its own process group,
+/// so it isn't killed with us, and no inherited stdio. A thread reaps it on exit.
+fn spawn_detached(mut command: Command) -> Result<(), String> {
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0);
+
+    let mut child = command.spawn().map_err(|e| e.to_string())?;
+    std::thread::spawn(move || {
+        child.wait().ok();
+    });
+    Ok(())
+}
+
 fn copy_to_clipboard(text: &str) -> Result<String, String> {
     use arboard::Clipboard;
     let mut clipboard = Clipboard::new().map_err(|e| e.to_string())?;
@@ -37,12 +269,7 @@ fn copy_to_clipboard(text: &str) -> Result<String, String> {
     Ok("Copied to clipboard".to_string())
 }
 
+// Returns the id of the new conversation, for follow-up questions
 async fn handle_ai_response(query: String, window: Window) -> Result<String, String> {
-    // Start AI response
-    tokio::spawn(async move {
-        if let Err(e) = ai_request(query, window).await {
-            eprintln!("AI response error: {}", e);
-        }
-    });
-    Ok("AI response started".to_string())
+    start_conversation(query, window)
 }
@@ -1,60 +1,50 @@
 use crate::config::load_config;
-use crate::models::{Message, OpenRouterRequest, OpenRouterResponse};
-use tauri::{command, Emitter, Window};
+use crate::models::{
+    AnthropicEvent, AnthropicRequest, Config, GeminiContent, GeminiPart, GeminiRequest,
+    GeminiResponse, Message, OllamaResponse, OpenRouterRequest, OpenRouterResponse,
+};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use std::collections::HashMap;
+use tauri::{Emitter, Window};
 use tokio_stream::StreamExt;
 
-#[command]
-pub async fn ai_request(query: String, window: Window) -> Result<(), String> {
-    let config = load_config().await.unwrap_or_default();
-
-    let api_key = match config.ai_service.as_str() {
-        "openrouter" => config.openrouter_api_key.clone(),
-        "openai" => config.openai_api_key.clone(),
-        _ => None,
-    };
+// The Messages API requires a limit on the reply length
+const ANTHROPIC_MAX_TOKENS: u32 = 4096;
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+// Characters left alone when a model name becomes part of a URL path
+const MODEL_NAME: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_');
 
-    let api_key = api_key.ok_or("API key not configured")?;
-
-    let client = reqwest::Client::new();
+/// Sends `messages` after the system prompt and streams the reply to the
+/// window as `ai_response_chunk` events. Returns the complete reply.
+pub async fn stream_chat(messages: Vec<Message>, window: &Window) -> Result<String, String> {
+    let config = load_config().await.unwrap_or_default();
+    let (backend, model) = create_backend(&config)?;
 
     // Create a comprehensive system prompt to give context to the AI
     let system_prompt = create_system_prompt();
 
-    let request_body = OpenRouterRequest {
-        model: config.default_model.clone(),
-        messages: vec![
-            Message {
-                role: "system".to_string(),
-                content: system_prompt,
-            },
-            Message {
-                role: "user".to_string(),
-                content: query,
-            },
-        ],
-        stream: true,
-    };
-
-    let url = get_api_url(&config.ai_service)?;
+    let reply = request_chat(
+        backend.as_ref(),
+        &model,
+        &system_prompt,
+        &messages,
+        |chunk| {
+            window
+                .emit("ai_response_chunk", chunk)
+                .map_err(|e| e.to_string())
+        },
+    )
+    .await?;
 
-    let response = client
-        .post(url)
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("Content-Type", "application/json")
-        .json(&request_body)
-        .send()
-        .await
+    // Emit completion event
+    window
+        .emit("ai_response_complete", ())
         .map_err(|e| e.to_string())?;
 
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("API request failed: {}", error_text));
-    }
-
-    process_ai_stream(response, window).await
+    Ok(reply)
 }
 
-fn create_system_prompt() -> String {
+pub fn create_system_prompt() -> String {
     "You are Lumina, an intelligent desktop search assistant integrated into a user's Linux desktop environment.
         
         Your role is to:
@@ -76,64 +66,784 @@ fn create_system_prompt() -> String {
         Format your responses with markdown when appropriate. Be helpful, accurate, and concise.".to_string()
 }
 
-fn get_api_url(service: &str) -> Result<&'static str, String> {
-    match service {
-        "openrouter" => Ok("https://openrouter.ai/api/v1/chat/completions"),
-        "openai" => Ok("https://api.openai.com/v1/chat/completions"),
-        _ => Err("Unsupported AI service".to_string()),
+/// A chat API that streams its replies, with its own request format and
+/// streaming event format.
+trait AiBackend: Send + Sync {
+    /// Builds the streaming request for a conversation.
+    fn request(
+        &self,
+        client: &reqwest::Client,
+        model: &str,
+        system_prompt: &str,
+        messages: &[Message],
+    ) -> reqwest::RequestBuilder;
+
+    /// Interprets one line of the streamed response body.
+    fn parse_line(&self, line: &str) -> StreamEvent;
+}
+
+// What one line of a streamed reply contributes
+#[derive(Debug, PartialEq)]
+enum StreamEvent {
+    Content(String),
+    Done,
+    Error(String),
+    Ignored,
+}
+
+/// The backend selected by `ai_service`, along with the model to request from it.
+fn create_backend(config: &Config) -> Result<(Box<dyn AiBackend>, String), String> {
+    let api_key = |key: &Option<String>| {
+        key.clone()
+            .filter(|key| !key.is_empty())
+            .ok_or("API key not configured")
+    };
+
+    let (backend, model): (Box<dyn AiBackend>, &str) = match config.ai_service.as_str() {
+        "openrouter" => (
+            Box::new(OpenAiBackend::new(
+                "https://openrouter.ai/api/v1",
+                Some(api_key(&config.openrouter_api_key)?),
+                HashMap::new(),
+            )),
+            &config.default_model,
+        ),
+        "openai" => (
+            Box::new(OpenAiBackend::new(
+                "https://api.openai.com/v1",
+                Some(api_key(&config.openai_api_key)?),
+                HashMap::new(),
+            )),
+            &config.openai_model,
+        ),
+        "anthropic" => (
+            Box::new(AnthropicBackend {
+                base_url: "https://api.anthropic.com".to_string(),
+                api_key: api_key(&config.anthropic_api_key)?,
+            }),
+            &config.anthropic_model,
+        ),
+        "gemini" => (
+            Box::new(GeminiBackend {
+                base_url: "https://generativelanguage.googleapis.com".to_string(),
+                api_key: api_key(&config.gemini_api_key)?,
+            }),
+            &config.gemini_model,
+        ),
+        "ollama" => (
+            Box::new(OllamaBackend {
+                base_url: config.ollama_url.clone(),
+            }),
+            &config.ollama_model,
+        ),
+        name => {
+            let provider = config
+                .ai_providers
+                .iter()
+                .find(|provider| provider.name == name)
+                .ok_or_else(|| format!("Unsupported AI service '{}'", name))?;
+            (
+                Box::new(OpenAiBackend::new(
+                    &provider.base_url,
+                    provider.api_key.clone().filter(|key| !key.is_empty()),
+                    provider.headers.clone(),
+                )),
+                provider.model.as_deref().unwrap_or_default(),
+            )
+        }
+    };
+
+    if model.trim().is_empty() {
+        return Err(format!("No model configured for '{}'", config.ai_service));
+    }
+    Ok((backend, model.trim().to_string()))
+}
+
+/// OpenAI chat completions, also spoken by OpenRouter and many local servers.
+struct OpenAiBackend {
+    url: String,
+    api_key: Option<String>,
+    headers: HashMap<String, String>,
+}
+
+impl OpenAiBackend {
+    fn new(base_url: &str, api_key: Option<String>, headers: HashMap<String, String>) -> Self {
+        Self {
+            url: format!("{}/chat/completions", base_url.trim_end_matches('/')),
+            api_key,
+            headers,
+        }
+    }
+}
+
+impl AiBackend for OpenAiBackend {
+    fn request(
+        &self,
+        client: &reqwest::Client,
+        model: &str,
+        system_prompt: &str,
+        messages: &[Message],
+    ) -> reqwest::RequestBuilder {
+        let request_body = OpenRouterRequest {
+            model: model.to_string(),
+            messages: with_system_message(system_prompt, messages),
+            stream: true,
+        };
+
+        let mut request = client.post(&self.url).json(&request_body);
+        if let Some(api_key) = &self.api_key {
+            request = request.header("Authorization", format!("Bearer {}", api_key));
+        }
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        request
+    }
+
+    fn parse_line(&self, line: &str) -> StreamEvent {
+        let Some(data) = sse_data(line) else {
+            return StreamEvent::Ignored;
+        };
+        if data == "[DONE]" {
+            return StreamEvent::Done;
+        }
+
+        let Ok(response) = serde_json::from_str::<OpenRouterResponse>(data) else {
+            return StreamEvent::Ignored;
+        };
+        if let Some(error) = response.error {
+            return StreamEvent::Error(error.message);
+        }
+        response
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|choice| choice.delta)
+            .and_then(|delta| delta.content)
+            .map_or(StreamEvent::Ignored, StreamEvent::Content)
+    }
+}
+
+/// The Anthropic Messages API.
+struct AnthropicBackend {
+    base_url: String,
+    api_key: String,
+}
+
+impl AiBackend for AnthropicBackend {
+    fn request(
+        &self,
+        client: &reqwest::Client,
+        model: &str,
+        system_prompt: &str,
+        messages: &[Message],
+    ) -> reqwest::RequestBuilder {
+        let request_body = AnthropicRequest {
+            model: model.to_string(),
+            max_tokens: ANTHROPIC_MAX_TOKENS,
+            system: system_prompt.to_string(),
+            messages: messages.to_vec(),
+            stream: true,
+        };
+
+        client
+            .post(format!(
+                "{}/v1/messages",
+                self.base_url.trim_end_matches('/')
+            ))
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .json(&request_body)
+    }
+
+    // Only the data lines matter; they repeat the event type
+    fn parse_line(&self, line: &str) -> StreamEvent {
+        let Some(event) =
+            sse_data(line).and_then(|data| serde_json::from_str::<AnthropicEvent>(data).ok())
+        else {
+            return StreamEvent::Ignored;
+        };
+
+        match event.kind.as_str() {
+            "content_block_delta" => event
+                .delta
+                .and_then(|delta| delta.text)
+                .map_or(StreamEvent::Ignored, StreamEvent::Content),
+            "message_stop" => StreamEvent::Done,
+            "error" => StreamEvent::Error(
+                event
+                    .error
+                    .map(|error| error.message)
+                    .unwrap_or_else(|| "Unknown error".to_string()),
+            ),
+            _ => StreamEvent::Ignored,
+        }
+    }
+}
+
+/// Google Gemini's `streamGenerateContent`, streamed as server-sent events.
+struct GeminiBackend {
+    base_url: String,
+    api_key: String,
+}
+
+impl AiBackend for GeminiBackend {
+    fn request(
+        &self,
+        client: &reqwest::Client,
+        model: &str,
+        system_prompt: &str,
+        messages: &[Message],
+    ) -> reqwest::RequestBuilder {
+        let content = |role: Option<&str>, text: &str| GeminiContent {
+            role: role.map(str::to_string),
+            parts: vec![GeminiPart {
+                text: text.to_string(),
+            }],
+        };
+        let request_body = GeminiRequest {
+            system_instruction: content(None, system_prompt),
+            contents: messages
+                .iter()
+                .map(|message| {
+                    // Gemini calls the assistant "model"
+                    let role = if message.role == "assistant" {
+                        "model"
+                    } else {
+                        "user"
+                    };
+                    content(Some(role), &message.content)
+                })
+                .collect(),
+        };
+
+        client
+            .post(format!(
+                "{}/v1beta/models/{}:streamGenerateContent?alt=sse",
+                self.base_url.trim_end_matches('/'),
+                utf8_percent_encode(model, MODEL_NAME)
+            ))
+            .header("x-goog-api-key", &self.api_key)
+            .json(&request_body)
+    }
+
+    // The stream simply ends after the last candidate
+    fn parse_line(&self, line: &str) -> StreamEvent {
+        let Some(response) =
+            sse_data(line).and_then(|data| serde_json::from_str::<GeminiResponse>(data).ok())
+        else {
+            return StreamEvent::Ignored;
+        };
+        if let Some(error) = response.error {
+            return StreamEvent::Error(error.message);
+        }
+
+        let text: String = response
+            .candidates
+            .into_iter()
+            .next()
+            .and_then(|candidate| candidate.content)
+            .map(|content| content.parts.into_iter().map(|part| part.text).collect())
+            .unwrap_or_default();
+        if text.is_empty() {
+            StreamEvent::Ignored
+        } else {
+            StreamEvent::Content(text)
+        }
+    }
+}
+
+/// Ollama's native `/api/chat`, streamed as one JSON object per line.
+struct OllamaBackend {
+    base_url: String,
+}
+
+impl AiBackend for OllamaBackend {
+    fn request(
+        &self,
+        client: &reqwest::Client,
+        model: &str,
+        system_prompt: &str,
+        messages: &[Message],
+    ) -> reqwest::RequestBuilder {
+        let request_body = OpenRouterRequest {
+            model: model.to_string(),
+            messages: with_system_message(system_prompt, messages),
+            stream: true,
+        };
+
+        client
+            .post(format!("{}/api/chat", self.base_url.trim_end_matches('/')))
+            .json(&request_body)
+    }
+
+    fn parse_line(&self, line: &str) -> StreamEvent {
+        let Ok(response) = serde_json::from_str::<OllamaResponse>(line) else {
+            return StreamEvent::Ignored;
+        };
+        if let Some(error) = response.error {
+            return StreamEvent::Error(error);
+        }
+        match response.message {
+            Some(message) if !message.content.is_empty() => StreamEvent::Content(message.content),
+            _ if response.done => StreamEvent::Done,
+            _ => StreamEvent::Ignored,
+        }
+    }
+}
+
+fn with_system_message(system_prompt: &str, messages: &[Message]) -> Vec<Message> {
+    std::iter::once(Message::new("system", system_prompt))
+        .chain(messages.iter().cloned())
+        .collect()
+}
+
+// Payload of a server-sent event `data:` line
+fn sse_data(line: &str) -> Option<&str> {
+    line.strip_prefix("data:").map(str::trim)
+}
+
+async fn request_chat(
+    backend: &dyn AiBackend,
+    model: &str,
+    system_prompt: &str,
+    messages: &[Message],
+    mut on_chunk: impl FnMut(&str) -> Result<(), String>,
+) -> Result<String, String> {
+    let client = reqwest::Client::new();
+    let response = backend
+        .request(&client, model, system_prompt, messages)
+        .header("Content-Type", "application/json")
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!("API request failed: {}", error_text));
     }
+
+    process_ai_stream(response, backend, &mut on_chunk).await
 }
 
-async fn process_ai_stream(response: reqwest::Response, window: Window) -> Result<(), String> {
+async fn process_ai_stream(
+    response: reqwest::Response,
+    backend: &dyn AiBackend,
+    on_chunk: &mut impl FnMut(&str) -> Result<(), String>,
+) -> Result<String, String> {
     let mut stream = response.bytes_stream();
-    let mut buffer = String::new();
+    // Bytes rather than text, since a chunk may end inside a multi-byte character
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut reply = String::new();
+
+    let mut handle_line = |line: &str, reply: &mut String| -> Result<bool, String> {
+        match backend.parse_line(line) {
+            StreamEvent::Content(content) => {
+                // Clean content before sending
+                let cleaned_content = content.replace('\r', "");
+                if !cleaned_content.is_empty() {
+                    reply.push_str(&cleaned_content);
+                    on_chunk(&cleaned_content)?;
+                }
+                Ok(false)
+            }
+            StreamEvent::Done => Ok(true),
+            StreamEvent::Error(message) => Err(format!("AI service error: {}", message)),
+            StreamEvent::Ignored => Ok(false),
+        }
+    };
 
     while let Some(chunk) = stream.next().await {
         let chunk = chunk.map_err(|e| e.to_string())?;
-        let chunk_str = String::from_utf8_lossy(&chunk);
-        buffer.push_str(&chunk_str);
+        buffer.extend_from_slice(&chunk);
 
         // Process complete lines
-        let mut start = 0;
-        while let Some(newline_pos) = buffer[start..].find('\n') {
-            let absolute_pos = start + newline_pos;
-            let line = buffer[start..absolute_pos].trim();
-            start = absolute_pos + 1;
-
-            if line.starts_with("data: ") {
-                let data = &line[6..];
-                if data == "[DONE]" {
+        while let Some(newline_pos) = buffer.iter().position(|&byte| byte == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=newline_pos).collect();
+            if handle_line(String::from_utf8_lossy(&line).trim(), &mut reply)? {
+                return Ok(reply);
+            }
+        }
+    }
+
+    // The last line may not end with a newline
+    handle_line(String::from_utf8_lossy(&buffer).trim(), &mut reply)?;
+    Ok(reply)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AiProviderConfig;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    // Answers a single request with `body` and returns the raw request,
+    // lowercased so header names can be compared directly
+    async fn mock_server(body: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+        mock_server_in_parts(vec![body.as_bytes()]).await
+    }
+
+    // Like `mock_server`, but sends the body in separate writes
+    async fn mock_server_in_parts(
+        parts: Vec<&'static [u8]>,
+    ) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0; 4096];
+            while !is_complete(&request) {
+                let read = socket.read(&mut buffer).await.unwrap();
+                if read == 0 {
                     break;
                 }
+                request.extend_from_slice(&buffer[..read]);
+            }
 
-                if let Ok(response) = serde_json::from_str::<OpenRouterResponse>(data) {
-                    if let Some(choice) = response.choices.first() {
-                        if let Some(delta) = &choice.delta {
-                            if let Some(content) = &delta.content {
-                                // Clean content before sending
-                                let cleaned_content = content.replace("\r", "");
-                                if !cleaned_content.is_empty() {
-                                    window
-                                        .emit("ai_response_chunk", &cleaned_content)
-                                        .map_err(|e| e.to_string())?;
-                                }
-                            }
-                        }
-                    }
-                }
+            let headers = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                parts.iter().map(|part| part.len()).sum::<usize>()
+            );
+            socket.write_all(headers.as_bytes()).await.unwrap();
+            for part in parts {
+                socket.write_all(part).await.unwrap();
+                socket.flush().await.unwrap();
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
             }
-        }
+            String::from_utf8_lossy(&request).to_lowercase()
+        });
+
+        (url, server)
+    }
+
+    fn is_complete(request: &[u8]) -> bool {
+        let request = String::from_utf8_lossy(request).to_lowercase();
+        let Some(header_end) = request.find("\r\n\r\n") else {
+            return false;
+        };
+        let content_length = request[..header_end]
+            .lines()
+            .find_map(|line| line.strip_prefix("content-length:"))
+            .and_then(|length| length.trim().parse::<usize>().ok())
+            .unwrap_or(0);
+        request.len() >= header_end + 4 + content_length
+    }
+
+    async fn chat(backend: &dyn AiBackend) -> (Result<String, String>, Vec<String>) {
+        let mut chunks = Vec::new();
+        let messages = [Message::new("user", "Hi")];
+        let reply = request_chat(backend, "test-model", "Be brief", &messages, |chunk| {
+            chunks.push(chunk.to_string());
+            Ok(())
+        })
+        .await;
+        (reply, chunks)
+    }
+
+    #[tokio::test]
+    async fn streams_from_ollama() {
+        let (url, server) = mock_server(concat!(
+            "{\"message\":{\"role\":\"assistant\",\"content\":\"Hello\"},\"done\":false}\n",
+            "{\"message\":{\"role\":\"assistant\",\"content\":\" world\"},\"done\":false}\n",
+            "{\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true}\n",
+        ))
+        .await;
+        let config = Config {
+            ai_service: "ollama".to_string(),
+            ollama_url: format!("{}/", url),
+            ollama_model: "test-model".to_string(),
+            ..Config::default()
+        };
 
-        // Remove processed part of buffer
-        if start > 0 {
-            buffer = buffer[start..].to_string();
+        let (reply, chunks) = chat(create_backend(&config).unwrap().0.as_ref()).await;
+        assert_eq!(reply.unwrap(), "Hello world");
+        assert_eq!(chunks, ["Hello", " world"]);
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("post /api/chat "), "{request}");
+        assert!(request.contains("\"model\":\"test-model\""));
+        assert!(request.contains("\"role\":\"system\""));
+        assert!(!request.contains("authorization:"));
+    }
+
+    #[tokio::test]
+    async fn fails_on_error_lines() {
+        let (url, _server) = mock_server("{\"error\":\"model 'llama9' not found\"}\n").await;
+        let backend = OllamaBackend { base_url: url };
+
+        let (reply, chunks) = chat(&backend).await;
+        assert_eq!(
+            reply.unwrap_err(),
+            "AI service error: model 'llama9' not found"
+        );
+        assert!(chunks.is_empty());
+    }
+
+    #[tokio::test]
+    async fn keeps_characters_split_between_chunks() {
+        let line = "{\"message\":{\"role\":\"assistant\",\"content\":\"日本 👋\"},\"done\":true}\n";
+        // Ends inside the three bytes of 本
+        let split = line.find('本').unwrap() + 1;
+        let (url, _server) =
+            mock_server_in_parts(vec![&line.as_bytes()[..split], &line.as_bytes()[split..]]).await;
+        let backend = OllamaBackend { base_url: url };
+
+        let (reply, chunks) = chat(&backend).await;
+        assert_eq!(reply.unwrap(), "日本 👋");
+        assert_eq!(chunks, ["日本 👋"]);
+    }
+
+    #[tokio::test]
+    async fn streams_from_openai_compatible_provider() {
+        let (url, server) = mock_server(concat!(
+            ": keep-alive\n\n",
+            "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Hel\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"lo\"}}]}\n\n",
+            "data: [DONE]\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"ignored\"}}]}\n\n",
+        ))
+        .await;
+        let config = Config {
+            ai_service: "local".to_string(),
+            ai_providers: vec![AiProviderConfig {
+                name: "local".to_string(),
+                base_url: format!("{}/v1/", url),
+                api_key: Some("secret".to_string()),
+                headers: HashMap::from([("X-Team".to_string(), "lumina".to_string())]),
+                model: Some("test-model".to_string()),
+            }],
+            ..Config::default()
+        };
+
+        let (reply, chunks) = chat(create_backend(&config).unwrap().0.as_ref()).await;
+        assert_eq!(reply.unwrap(), "Hello");
+        assert_eq!(chunks, ["Hel", "lo"]);
+
+        let request = server.await.unwrap();
+        assert!(
+            request.starts_with("post /v1/chat/completions "),
+            "{request}"
+        );
+        assert!(request.contains("authorization: bearer secret"));
+        assert!(request.contains("x-team: lumina"));
+    }
+
+    #[test]
+    fn rejects_unknown_services() {
+        let config = Config {
+            ai_service: "missing".to_string(),
+            ..Config::default()
+        };
+        assert!(create_backend(&config).is_err());
+    }
+
+    #[test]
+    fn uses_the_model_of_the_selected_service() {
+        let config = Config {
+            ai_service: "anthropic".to_string(),
+            anthropic_api_key: Some("secret".to_string()),
+            ..Config::default()
+        };
+        assert_eq!(
+            create_backend(&config).unwrap().1,
+            "claude-3-5-sonnet-latest"
+        );
+
+        let config = Config {
+            ai_service: "ollama".to_string(),
+            ..Config::default()
+        };
+        assert_eq!(create_backend(&config).unwrap().1, "llama3.2");
+
+        let config = Config {
+            ai_service: "local".to_string(),
+            ai_providers: vec![AiProviderConfig {
+                name: "local".to_string(),
+                base_url: "http://localhost:8080/v1".to_string(),
+                api_key: None,
+                headers: HashMap::new(),
+                model: None,
+            }],
+            ..Config::default()
+        };
+        assert_eq!(
+            create_backend(&config).err().unwrap(),
+            "No model configured for 'local'"
+        );
+    }
+
+    #[test]
+    fn requires_api_keys() {
+        for service in ["openrouter", "openai", "anthropic", "gemini"] {
+            let config = Config {
+                ai_service: service.to_string(),
+                ..Config::default()
+            };
+            assert!(create_backend(&config).is_err(), "{service}");
         }
     }
 
-    // Emit completion event
-    window
-        .emit("ai_response_complete", ())
-        .map_err(|e| e.to_string())?;
+    #[tokio::test]
+    async fn streams_from_anthropic() {
+        let (url, server) = mock_server(concat!(
+            "event: message_start\n",
+            "data: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_1\"}}\n\n",
+            "event: content_block_delta\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hi\"}}\n\n",
+            "event: message_stop\n",
+            "data: {\"type\":\"message_stop\"}\n\n",
+        ))
+        .await;
+        let backend = AnthropicBackend {
+            base_url: url,
+            api_key: "secret".to_string(),
+        };
+
+        let (reply, chunks) = chat(&backend).await;
+        assert_eq!(reply.unwrap(), "Hi");
+        assert_eq!(chunks, ["Hi"]);
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("post /v1/messages "), "{request}");
+        assert!(request.contains("x-api-key: secret"));
+        assert!(request.contains("anthropic-version: 2023-06-01"));
+        assert!(request.contains("\"system\":\"be brief\""));
+        assert!(!request.contains("\"role\":\"system\""));
+    }
+
+    #[tokio::test]
+    async fn streams_from_gemini() {
+        let (url, server) = mock_server(concat!(
+            "data: {\"candidates\":[{\"content\":{\"role\":\"model\",\"parts\":[{\"text\":\"Hel\"}]}}]}\r\n\r\n",
+            "data: {\"candidates\":[{\"content\":{\"role\":\"model\",\"parts\":[{\"text\":\"lo\"}]},\"finishReason\":\"STOP\"}]}\r\n\r\n",
+        ))
+        .await;
+        let backend = GeminiBackend {
+            base_url: url,
+            api_key: "secret".to_string(),
+        };
+
+        let (reply, chunks) = chat(&backend).await;
+        assert_eq!(reply.unwrap(), "Hello");
+        assert_eq!(chunks, ["Hel", "lo"]);
+
+        let request = server.await.unwrap();
+        assert!(
+            request.starts_with("post /v1beta/models/test-model:streamgeneratecontent?alt=sse "),
+            "{request}"
+        );
+        assert!(request.contains("x-goog-api-key: secret"));
+        assert!(request.contains("\"systeminstruction\":{\"parts\":[{\"text\":\"be brief\"}]}"));
+    }
 
-    Ok(())
+    #[test]
+    fn escapes_gemini_model_names() {
+        let backend = GeminiBackend {
+            base_url: "http://localhost".to_string(),
+            api_key: String::new(),
+        };
+        let client = reqwest::Client::new();
+        let request = backend
+            .request(&client, "gemini-1.5-pro/../x?y", "", &[])
+            .build()
+            .unwrap();
+        assert_eq!(
+            request.url().path(),
+            "/v1beta/models/gemini-1.5-pro%2F..%2Fx%3Fy:streamGenerateContent"
+        );
+    }
+
+    #[test]
+    fn parses_openai_events() {
+        let backend = OpenAiBackend::new("http://localhost", None, HashMap::new());
+        assert_eq!(
+            backend.parse_line(r#"data: {"choices":[{"delta":{"content":"Hi"}}]}"#),
+            StreamEvent::Content("Hi".to_string())
+        );
+        assert_eq!(backend.parse_line("data: [DONE]"), StreamEvent::Done);
+        assert_eq!(
+            backend.parse_line(r#"data: {"error":{"message":"Rate limit exceeded","code":429}}"#),
+            StreamEvent::Error("Rate limit exceeded".to_string())
+        );
+        assert_eq!(backend.parse_line(": keep-alive"), StreamEvent::Ignored);
+        assert_eq!(backend.parse_line(""), StreamEvent::Ignored);
+    }
+
+    #[test]
+    fn parses_anthropic_events() {
+        let backend = AnthropicBackend {
+            base_url: String::new(),
+            api_key: String::new(),
+        };
+        assert_eq!(
+            backend.parse_line(
+                r#"data: {"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hi"}}"#
+            ),
+            StreamEvent::Content("Hi".to_string())
+        );
+        assert_eq!(
+            backend.parse_line(r#"data: {"type":"message_stop"}"#),
+            StreamEvent::Done
+        );
+        assert_eq!(
+            backend.parse_line(
+                r#"data: {"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#
+            ),
+            StreamEvent::Error("Overloaded".to_string())
+        );
+        assert_eq!(
+            backend.parse_line(r#"data: {"type":"ping"}"#),
+            StreamEvent::Ignored
+        );
+        assert_eq!(
+            backend.parse_line("event: content_block_delta"),
+            StreamEvent::Ignored
+        );
+    }
+
+    #[test]
+    fn parses_gemini_events() {
+        let backend = GeminiBackend {
+            base_url: String::new(),
+            api_key: String::new(),
+        };
+        assert_eq!(
+            backend.parse_line(
+                r#"data: {"candidates":[{"content":{"parts":[{"text":"Hel"},{"text":"lo"}]}}]}"#
+            ),
+            StreamEvent::Content("Hello".to_string())
+        );
+        assert_eq!(
+            backend.parse_line(r#"data: {"candidates":[{"finishReason":"STOP"}]}"#),
+            StreamEvent::Ignored
+        );
+        assert_eq!(
+            backend.parse_line(r#"data: {"error":{"code":400,"message":"Bad key"}}"#),
+            StreamEvent::Error("Bad key".to_string())
+        );
+    }
+
+    #[test]
+    fn parses_ollama_lines() {
+        let backend = OllamaBackend {
+            base_url: String::new(),
+        };
+        assert_eq!(
+            backend.parse_line(r#"{"message":{"role":"assistant","content":"Hi"},"done":false}"#),
+            StreamEvent::Content("Hi".to_string())
+        );
+        assert_eq!(
+            backend.parse_line(r#"{"message":{"role":"assistant","content":""},"done":true}"#),
+            StreamEvent::Done
+        );
+        assert_eq!(
+            backend.parse_line(r#"{"error":"model 'llama9' not found"}"#),
+            StreamEvent::Error("model 'llama9' not found".to_string())
+        );
+        assert_eq!(backend.parse_line("not json"), StreamEvent::Ignored);
+    }
 }
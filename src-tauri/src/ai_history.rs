@@ -0,0 +1,156 @@
+use crate::config::write_atomically;
+use crate::models::Message;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{OnceLock, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::command;
+
+// Completed AI exchanges, loaded from disk on first use
+static EXCHANGES: OnceLock<RwLock<Vec<AiExchange>>> = OnceLock::new();
+
+/// A question and the AI's complete answer to it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AiExchange {
+    pub id: String,
+    /// Exchanges of one conversation share this id
+    pub conversation_id: String,
+    pub question: String,
+    pub answer: String,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    // Lowercased answer, kept so searches don't lowercase every answer per keystroke
+    #[serde(skip)]
+    answer_lower: String,
+}
+
+impl AiExchange {
+    /// Whether the answer contains `lower_query`, which must be lowercase.
+    pub fn answer_contains(&self, lower_query: &str) -> bool {
+        self.answer_lower.contains(lower_query)
+    }
+}
+
+fn get_exchanges() -> &'static RwLock<Vec<AiExchange>> {
+    EXCHANGES.get_or_init(|| RwLock::new(load_exchanges().unwrap_or_default()))
+}
+
+/// Saves a completed exchange, appending it to the history file.
+pub fn record_exchange(conversation_id: &str, question: &str, answer: &str) -> Result<(), String> {
+    let exchange = AiExchange {
+        id: uuid::Uuid::new_v4().to_string(),
+        conversation_id: conversation_id.to_string(),
+        question: question.to_string(),
+        answer: answer.to_string(),
+        answer_lower: answer.to_lowercase(),
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default(),
+    };
+
+    let mut exchanges = get_exchanges().write().map_err(|e| e.to_string())?;
+    append_exchange(&exchange)?;
+    exchanges.push(exchange);
+    Ok(())
+}
+
+/// Runs `f` on the saved exchanges, oldest first, without copying them.
+pub fn with_exchanges<T>(f: impl FnOnce(&[AiExchange]) -> T) -> T {
+    match get_exchanges().read() {
+        Ok(exchanges) => f(&exchanges),
+        Err(_) => f(&[]),
+    }
+}
+
+/// The conversation leading up to and including an exchange, as messages
+/// for the AI, along with the conversation id.
+pub fn conversation_until(exchange_id: &str) -> Result<(String, Vec<Message>), String> {
+    let exchanges = get_exchanges().read().map_err(|e| e.to_string())?;
+    let position = exchanges
+        .iter()
+        .position(|exchange| exchange.id == exchange_id)
+        .ok_or("Unknown AI exchange")?;
+    let conversation_id = &exchanges[position].conversation_id;
+
+    // Exchanges are stored in the order they were answered
+    let messages = exchanges[..=position]
+        .iter()
+        .filter(|earlier| &earlier.conversation_id == conversation_id)
+        .flat_map(|earlier| {
+            [
+                Message::new("user", earlier.question.as_str()),
+                Message::new("assistant", earlier.answer.as_str()),
+            ]
+        })
+        .collect();
+    Ok((conversation_id.clone(), messages))
+}
+
+/// Deletes the exchanges with the given ids.
+#[command]
+pub fn delete_ai_exchanges(ids: Vec<String>) -> Result<(), String> {
+    let mut exchanges = get_exchanges().write().map_err(|e| e.to_string())?;
+    exchanges.retain(|exchange| !ids.contains(&exchange.id));
+    save_exchanges(&exchanges)
+}
+
+/// Deletes every saved exchange.
+#[command]
+pub fn clear_ai_history() -> Result<(), String> {
+    let mut exchanges = get_exchanges().write().map_err(|e| e.to_string())?;
+    exchanges.clear();
+    save_exchanges(&exchanges)
+}
+
+fn get_exchanges_path() -> Result<PathBuf, String> {
+    let data_dir = dirs::data_dir()
+        .ok_or("Could not find data directory")?
+        .join("lumina");
+    Ok(data_dir.join("ai_history.jsonl"))
+}
+
+// One JSON object per line, so saving an exchange only appends
+fn load_exchanges() -> Result<Vec<AiExchange>, String> {
+    let content = std::fs::read_to_string(get_exchanges_path()?).map_err(|e| e.to_string())?;
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str::<AiExchange>(line) {
+            Ok(exchange) => Some(AiExchange {
+                answer_lower: exchange.answer.to_lowercase(),
+                ..exchange
+            }),
+            Err(e) => {
+                eprintln!("Skipping malformed AI history entry: {}", e);
+                None
+            }
+        })
+        .collect())
+}
+
+fn append_exchange(exchange: &AiExchange) -> Result<(), String> {
+    let exchanges_path = get_exchanges_path()?;
+    if let Some(data_dir) = exchanges_path.parent() {
+        std::fs::create_dir_all(data_dir).map_err(|e| e.to_string())?;
+    }
+
+    let mut line = serde_json::to_string(exchange).map_err(|e| e.to_string())?;
+    line.push('\n');
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(exchanges_path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|e| e.to_string())
+}
+
+fn save_exchanges(exchanges: &[AiExchange]) -> Result<(), String> {
+    let mut content = String::new();
+    for exchange in exchanges {
+        content.push_str(&serde_json::to_string(exchange).map_err(|e| e.to_string())?);
+        content.push('\n');
+    }
+    write_atomically(&get_exchanges_path()?, content)
+}
@@ -1,4 +1,5 @@
 use crate::models::Config;
+use std::path::{Path, PathBuf};
 use tauri::command;
 
 #[command]
@@ -12,17 +13,23 @@ pub async fn save_config(config: Config) -> Result<(), String> {
         .ok_or("Could not find config directory")?
         .join("lumina");
 
-    std::fs::create_dir_all(&config_dir).map_err(|e| e.to_string())?;
-
     let config_path = config_dir.join("config.json");
     let config_json = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
 
-    std::fs::write(config_path, config_json).map_err(|e| e.to_string())?;
+    write_atomically(&config_path, config_json)?;
+
+    // Search directories may have changed
+    crate::search::index::request_refresh();
 
     Ok(())
 }
 
 pub async fn load_config() -> Result<Config, String> {
+    read_config()
+}
+
+/// Reads the config file synchronously, for threads outside the async runtime.
+pub fn read_config() -> Result<Config, String> {
     let config_dir = dirs::config_dir()
         .ok_or("Could not find config directory")?
         .join("lumina");
@@ -38,3 +45,17 @@ pub async fn load_config() -> Result<Config, String> {
 
     Ok(config)
 }
+
+/// Replaces the file at `path`, creating its directory if needed. The contents
+/// go to a temporary file first, so a crash never leaves a truncated file.
+pub fn write_atomically(path: &Path, contents: impl AsRef<[u8]>) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let temp_path = PathBuf::from(temp_path);
+    std::fs::write(&temp_path, contents).map_err(|e| e.to_string())?;
+    std::fs::rename(temp_path, path).map_err(|e| e.to_string())
+}
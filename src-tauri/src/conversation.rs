@@ -0,0 +1,319 @@
+use crate::ai::{create_system_prompt, stream_chat};
+use crate::ai_history;
+use crate::config::load_config;
+use crate::models::Message;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+use tauri::async_runtime::JoinHandle;
+use tauri::{command, AppHandle, Emitter, Manager, Window};
+
+// Rough size of a token in characters, good enough for budgeting
+const CHARS_PER_TOKEN: usize = 4;
+// Tokens spent on the role and separators of each message
+const MESSAGE_OVERHEAD_TOKENS: usize = 4;
+// Sessions the frontend never reset (e.g. after a reload) are dropped beyond
+// this many, least recently used first; reopening restores them from history
+const MAX_CONVERSATIONS: usize = 20;
+
+// Every open conversation, by id
+static CONVERSATIONS: OnceLock<Mutex<HashMap<String, Conversation>>> = OnceLock::new();
+
+// The reply currently being streamed; starting another one cancels it
+static CURRENT_REPLY: OnceLock<Mutex<Option<RunningReply>>> = OnceLock::new();
+static NEXT_REPLY_ID: AtomicU64 = AtomicU64::new(0);
+
+struct Conversation {
+    /// History without the system prompt
+    messages: Vec<Message>,
+    last_used: Instant,
+}
+
+struct RunningReply {
+    id: u64,
+    conversation_id: String,
+    task: JoinHandle<()>,
+}
+
+fn get_conversations() -> &'static Mutex<HashMap<String, Conversation>> {
+    CONVERSATIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn get_current_reply() -> &'static Mutex<Option<RunningReply>> {
+    CURRENT_REPLY.get_or_init(|| Mutex::new(None))
+}
+
+fn open_conversation(conversation_id: &str, messages: Vec<Message>) -> Result<(), String> {
+    let mut conversations = get_conversations().lock().map_err(|e| e.to_string())?;
+    if conversations.len() >= MAX_CONVERSATIONS && !conversations.contains_key(conversation_id) {
+        let oldest = conversations
+            .iter()
+            .min_by_key(|(_, conversation)| conversation.last_used)
+            .map(|(id, _)| id.clone());
+        if let Some(oldest) = oldest {
+            conversations.remove(&oldest);
+        }
+    }
+
+    conversations.insert(
+        conversation_id.to_string(),
+        Conversation {
+            messages,
+            last_used: Instant::now(),
+        },
+    );
+    Ok(())
+}
+
+/// Starts a conversation with `message` and streams the reply. Returns the
+/// conversation id used to ask follow-up questions.
+#[command]
+pub fn start_conversation(message: String, window: Window) -> Result<String, String> {
+    let conversation_id = uuid::Uuid::new_v4().to_string();
+    open_conversation(&conversation_id, Vec::new())?;
+
+    send_message(conversation_id.clone(), message, window)?;
+    Ok(conversation_id)
+}
+
+/// Asks a follow-up question; the AI sees the earlier turns of the conversation.
+#[command]
+pub fn continue_conversation(
+    conversation_id: String,
+    message: String,
+    window: Window,
+) -> Result<(), String> {
+    let conversations = get_conversations().lock().map_err(|e| e.to_string())?;
+    if !conversations.contains_key(&conversation_id) {
+        return Err("Unknown conversation".to_string());
+    }
+    drop(conversations);
+
+    send_message(conversation_id, message, window)
+}
+
+/// Reopens a saved exchange as a conversation, showing its answer again so
+/// that follow-up questions can be asked. Returns the conversation id.
+pub fn reopen_conversation(exchange_id: &str, window: &Window) -> Result<String, String> {
+    // A reply still streaming would mix its chunks into the reopened answer
+    cancel_reply(window.app_handle());
+
+    let (conversation_id, messages) = ai_history::conversation_until(exchange_id)?;
+    let answer = messages
+        .last()
+        .map(|message| message.content.clone())
+        .unwrap_or_default();
+    open_conversation(&conversation_id, messages)?;
+
+    window
+        .emit("ai_response_chunk", &answer)
+        .map_err(|e| e.to_string())?;
+    window
+        .emit("ai_response_complete", ())
+        .map_err(|e| e.to_string())?;
+    Ok(conversation_id)
+}
+
+/// Forgets the history of a conversation.
+#[command]
+pub fn reset_conversation(conversation_id: String) -> Result<(), String> {
+    get_conversations()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove(&conversation_id);
+    Ok(())
+}
+
+/// Stops streaming the current reply, e.g. because the user pressed Escape.
+#[command]
+pub fn cancel_ai_request(window: Window) {
+    cancel_reply(window.app_handle());
+}
+
+/// Aborts the reply being streamed, if any, and emits `ai_response_cancelled`
+/// with the id of its conversation. The aborted task removes the unanswered
+/// question itself.
+pub fn cancel_reply(app: &AppHandle) {
+    let Some(reply) = get_current_reply()
+        .lock()
+        .ok()
+        .and_then(|mut current| current.take())
+    else {
+        return;
+    };
+    reply.task.abort();
+
+    if let Err(e) = app.emit("ai_response_cancelled", &reply.conversation_id) {
+        eprintln!("Failed to emit AI cancellation: {}", e);
+    }
+}
+
+fn send_message(conversation_id: String, message: String, window: Window) -> Result<(), String> {
+    cancel_reply(window.app_handle());
+
+    let mut current = get_current_reply().lock().map_err(|e| e.to_string())?;
+    let id = NEXT_REPLY_ID.fetch_add(1, Ordering::SeqCst);
+    let task_conversation_id = conversation_id.clone();
+    let task = tauri::async_runtime::spawn(async move {
+        if let Err(e) = reply(&task_conversation_id, message, &window).await {
+            eprintln!("AI response error: {}", e);
+        }
+
+        if let Ok(mut current) = get_current_reply().lock() {
+            if current.as_ref().is_some_and(|reply| reply.id == id) {
+                current.take();
+            }
+        }
+    });
+
+    *current = Some(RunningReply {
+        id,
+        conversation_id,
+        task,
+    });
+    Ok(())
+}
+
+async fn reply(conversation_id: &str, message: String, window: &Window) -> Result<(), String> {
+    let config = load_config().await.unwrap_or_default();
+    let budget = config
+        .ai_context_tokens
+        .saturating_sub(estimate_tokens(&create_system_prompt()));
+
+    let (messages, position) = {
+        let mut conversations = get_conversations().lock().map_err(|e| e.to_string())?;
+        let conversation = conversations
+            .get_mut(conversation_id)
+            .ok_or("Unknown conversation")?;
+        conversation.last_used = Instant::now();
+        let history = &mut conversation.messages;
+        history.push(Message::new("user", message.as_str()));
+        (fit_to_budget(history, budget), history.len() - 1)
+    };
+    // Dropped along with the task if the reply is cancelled mid-stream
+    let mut question = PendingQuestion {
+        conversation_id,
+        position,
+        content: &message,
+        answered: false,
+    };
+
+    let reply = stream_chat(messages, window).await?;
+    // Nothing worth finding again
+    if !reply.trim().is_empty() {
+        if let Err(e) = ai_history::record_exchange(conversation_id, &message, &reply) {
+            eprintln!("Failed to save AI exchange: {}", e);
+        }
+    }
+
+    question.answered = true;
+    let mut conversations = get_conversations().lock().map_err(|e| e.to_string())?;
+    // The conversation may have been reset while the reply was streaming
+    if let Some(conversation) = conversations.get_mut(conversation_id) {
+        conversation.messages.push(Message::new("assistant", reply));
+    }
+    Ok(())
+}
+
+// Removes the question from the history unless it was answered, keeping the
+// history alternating between user and assistant. An aborted task is dropped
+// asynchronously, possibly after the next question was asked, so the question
+// is found by position rather than by being last.
+struct PendingQuestion<'a> {
+    conversation_id: &'a str,
+    position: usize,
+    content: &'a str,
+    answered: bool,
+}
+
+impl Drop for PendingQuestion<'_> {
+    fn drop(&mut self) {
+        if self.answered {
+            return;
+        }
+        if let Ok(mut conversations) = get_conversations().lock() {
+            if let Some(conversation) = conversations.get_mut(self.conversation_id) {
+                let history = &mut conversation.messages;
+                if history.get(self.position).is_some_and(|question| {
+                    question.role == "user" && question.content == self.content
+                }) {
+                    history.remove(self.position);
+                }
+            }
+        }
+    }
+}
+
+/// The most recent messages that fit into `budget` tokens. The latest message
+/// is always kept, and the kept history never starts with an AI reply.
+fn fit_to_budget(history: &[Message], budget: usize) -> Vec<Message> {
+    let mut used = 0;
+    let mut first = history.len().saturating_sub(1);
+    while first > 0 {
+        used += estimate_tokens(&history[first].content);
+        let cost = estimate_tokens(&history[first - 1].content);
+        if used + cost > budget {
+            break;
+        }
+        first -= 1;
+    }
+
+    while first + 1 < history.len() && history[first].role != "user" {
+        first += 1;
+    }
+    history[first..].to_vec()
+}
+
+fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN) + MESSAGE_OVERHEAD_TOKENS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Each message costs 1 + MESSAGE_OVERHEAD_TOKENS = 5 tokens
+    fn history(roles: &[&str]) -> Vec<Message> {
+        roles
+            .iter()
+            .enumerate()
+            .map(|(i, role)| Message::new(role, format!("m{:02}", i)))
+            .collect()
+    }
+
+    fn contents(messages: &[Message]) -> Vec<&str> {
+        messages.iter().map(|m| m.content.as_str()).collect()
+    }
+
+    #[test]
+    fn keeps_everything_that_fits() {
+        let history = history(&["user", "assistant", "user"]);
+        assert_eq!(
+            contents(&fit_to_budget(&history, 15)),
+            ["m00", "m01", "m02"]
+        );
+        assert_eq!(
+            contents(&fit_to_budget(&history, 1000)),
+            ["m00", "m01", "m02"]
+        );
+    }
+
+    #[test]
+    fn keeps_the_latest_message_over_budget() {
+        let history = history(&["user", "assistant", "user"]);
+        assert_eq!(contents(&fit_to_budget(&history, 0)), ["m02"]);
+        assert_eq!(contents(&fit_to_budget(&[], 0)), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn never_starts_with_a_reply() {
+        let history = history(&["user", "assistant", "user", "assistant", "user"]);
+        // Room for the last four, but the oldest of them is an AI reply
+        assert_eq!(
+            contents(&fit_to_budget(&history, 20)),
+            ["m02", "m03", "m04"]
+        );
+        assert_eq!(contents(&fit_to_budget(&history, 10)), ["m04"]);
+    }
+}
@@ -0,0 +1,598 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+const DESKTOP_ENTRY_GROUP: &str = "Desktop Entry";
+
+/// A parsed freedesktop.org Desktop Entry file.
+#[derive(Debug, Clone)]
+pub struct DesktopEntry {
+    pub path: PathBuf,
+    /// Desktop file ID, e.g. `org.mozilla.firefox.desktop`
+    pub id: String,
+    pub entry_type: String,
+    pub name: String,
+    pub generic_name: Option<String>,
+    pub comment: Option<String>,
+    pub keywords: Vec<String>,
+    pub icon: Option<String>,
+    pub exec: Option<String>,
+    /// Working directory to launch the program in
+    pub working_dir: Option<String>,
+    pub terminal: bool,
+    pub no_display: bool,
+    pub hidden: bool,
+    pub only_show_in: Vec<String>,
+    pub not_show_in: Vec<String>,
+    /// MIME types the application declares it can open
+    pub mime_types: Vec<String>,
+    /// Additional `[Desktop Action ...]` groups, in the order listed by `Actions`
+    pub actions: Vec<DesktopAction>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DesktopAction {
+    pub id: String,
+    pub name: String,
+    pub icon: Option<String>,
+    pub exec: Option<String>,
+}
+
+impl DesktopEntry {
+    pub fn parse(path: &Path, id: String) -> Option<DesktopEntry> {
+        let content = std::fs::read_to_string(path).ok()?;
+        let groups = parse_groups(&content);
+        let group = groups.get(DESKTOP_ENTRY_GROUP)?;
+        let locales = current_locales();
+
+        Some(DesktopEntry {
+            path: path.to_path_buf(),
+            id,
+            entry_type: string(group, "Type").unwrap_or_default(),
+            name: localized(group, "Name", &locales)?,
+            generic_name: localized(group, "GenericName", &locales),
+            comment: localized(group, "Comment", &locales),
+            keywords: localized(group, "Keywords", &locales)
+                .map(|keywords| split_list(&keywords))
+                .unwrap_or_default(),
+            icon: string(group, "Icon"),
+            exec: string(group, "Exec"),
+            working_dir: string(group, "Path").filter(|dir| !dir.is_empty()),
+            terminal: is_true(group.get("Terminal")),
+            no_display: is_true(group.get("NoDisplay")),
+            hidden: is_true(group.get("Hidden")),
+            only_show_in: group
+                .get("OnlyShowIn")
+                .map(|desktops| split_list(desktops))
+                .unwrap_or_default(),
+            not_show_in: group
+                .get("NotShowIn")
+                .map(|desktops| split_list(desktops))
+                .unwrap_or_default(),
+            mime_types: group
+                .get("MimeType")
+                .map(|types| split_list(types))
+                .unwrap_or_default(),
+            actions: parse_actions(group, &groups, &locales),
+        })
+    }
+
+    /// Whether the entry should be offered in the current desktop environment.
+    pub fn should_show(&self) -> bool {
+        if self.entry_type != "Application" || self.no_display || self.hidden {
+            return false;
+        }
+
+        let current_desktops = current_desktops();
+        if !self.only_show_in.is_empty()
+            && !self
+                .only_show_in
+                .iter()
+                .any(|desktop| current_desktops.contains(desktop))
+        {
+            return false;
+        }
+
+        !self
+            .not_show_in
+            .iter()
+            .any(|desktop| current_desktops.contains(desktop))
+    }
+
+    /// Splits the Exec key into program arguments, expanding field codes.
+    /// `files` are substituted for `%f`, `%F`, `%u` and `%U`.
+    pub fn exec_arguments(&self, files: &[String]) -> Result<Vec<String>, String> {
+        let exec = self
+            .exec
+            .as_deref()
+            .ok_or("Desktop entry has no Exec key")?;
+        expand_exec(exec, self, files)
+    }
+
+    /// Program arguments for one of the entry's desktop actions.
+    pub fn action_exec_arguments(&self, action_id: &str) -> Result<Vec<String>, String> {
+        let action = self
+            .actions
+            .iter()
+            .find(|action| action.id == action_id)
+            .ok_or_else(|| format!("Unknown desktop action '{}'", action_id))?;
+        let exec = action
+            .exec
+            .as_deref()
+            .ok_or("Desktop action has no Exec key")?;
+        expand_exec(exec, self, &[])
+    }
+}
+
+pub(crate) fn expand_exec(
+    exec: &str,
+    entry: &DesktopEntry,
+    files: &[String],
+) -> Result<Vec<String>, String> {
+    let mut arguments = Vec::new();
+
+    for token in split_exec(exec)? {
+        match token.as_str() {
+            "%f" | "%u" => arguments.extend(files.first().cloned()),
+            "%F" | "%U" => arguments.extend(files.iter().cloned()),
+            "%d" | "%D" | "%n" | "%N" | "%v" | "%m" => {}
+            "%i" => {
+                if let Some(icon) = &entry.icon {
+                    arguments.push("--icon".to_string());
+                    arguments.push(icon.clone());
+                }
+            }
+            _ => arguments.push(expand_field_codes(&token, entry, files.first())),
+        }
+    }
+
+    if arguments.is_empty() {
+        return Err("Exec key is empty".to_string());
+    }
+    Ok(arguments)
+}
+
+fn expand_field_codes(argument: &str, entry: &DesktopEntry, file: Option<&String>) -> String {
+    let mut expanded = String::with_capacity(argument.len());
+    let mut chars = argument.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => expanded.push('%'),
+            Some('c') => expanded.push_str(&entry.name),
+            Some('k') => expanded.push_str(&entry.path.to_string_lossy()),
+            Some('i') => {
+                if let Some(icon) = &entry.icon {
+                    expanded.push_str(icon);
+                }
+            }
+            // Inside a larger argument only a single file can be substituted
+            Some('f' | 'u') => {
+                if let Some(file) = file {
+                    expanded.push_str(file);
+                }
+            }
+            // Deprecated codes expand to nothing
+            Some(_) | None => {}
+        }
+    }
+
+    expanded
+}
+
+/// Splits an Exec value into arguments, honoring double-quoted arguments and
+/// their backslash escapes.
+fn split_exec(exec: &str) -> Result<Vec<String>, String> {
+    let mut arguments = Vec::new();
+    let mut current = String::new();
+    let mut in_argument = false;
+    let mut chars = exec.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_argument = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(escaped @ ('"' | '`' | '$' | '\\')) => current.push(escaped),
+                            Some(other) => {
+                                current.push('\\');
+                                current.push(other);
+                            }
+                            None => return Err("Unterminated escape in Exec key".to_string()),
+                        },
+                        Some(other) => current.push(other),
+                        None => return Err("Unterminated quote in Exec key".to_string()),
+                    }
+                }
+            }
+            ' ' | '\t' => {
+                if in_argument {
+                    arguments.push(std::mem::take(&mut current));
+                    in_argument = false;
+                }
+            }
+            _ => {
+                in_argument = true;
+                current.push(c);
+            }
+        }
+    }
+    if in_argument {
+        arguments.push(current);
+    }
+
+    Ok(arguments)
+}
+
+fn parse_actions(
+    group: &HashMap<String, String>,
+    groups: &HashMap<String, HashMap<String, String>>,
+    locales: &[String],
+) -> Vec<DesktopAction> {
+    let Some(action_ids) = group.get("Actions") else {
+        return Vec::new();
+    };
+
+    split_list(action_ids)
+        .into_iter()
+        .filter_map(|id| {
+            let action_group = groups.get(&format!("Desktop Action {}", id))?;
+            Some(DesktopAction {
+                name: localized(action_group, "Name", locales)?,
+                icon: string(action_group, "Icon"),
+                exec: string(action_group, "Exec"),
+                id,
+            })
+        })
+        .collect()
+}
+
+/// Directories searched for desktop entries, most important first.
+pub fn application_directories() -> Vec<PathBuf> {
+    let mut directories = Vec::new();
+
+    if let Some(data_home) = data_home() {
+        directories.push(data_home.join("applications"));
+        directories.push(data_home.join("flatpak/exports/share/applications"));
+    }
+    for data_dir in system_data_dirs() {
+        directories.push(data_dir.join("applications"));
+    }
+    directories.push(PathBuf::from("/var/lib/flatpak/exports/share/applications"));
+
+    let mut seen = std::collections::HashSet::new();
+    directories.retain(|dir| seen.insert(dir.clone()));
+    directories
+}
+
+/// `$XDG_DATA_HOME`, defaulting to `~/.local/share`.
+pub(crate) fn data_home() -> Option<PathBuf> {
+    std::env::var("XDG_DATA_HOME")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".local/share")))
+}
+
+/// `$XDG_DATA_DIRS`, defaulting to `/usr/local/share:/usr/share`.
+pub(crate) fn system_data_dirs() -> Vec<PathBuf> {
+    std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string())
+        .split(':')
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+/// Parses the key/value pairs of every group, keeping the first occurrence of a key.
+/// Values stay escaped, since unescaping depends on whether they are lists;
+/// read them with `string`, `localized` or `split_list`.
+pub(crate) fn parse_groups(content: &str) -> HashMap<String, HashMap<String, String>> {
+    let mut groups: HashMap<String, HashMap<String, String>> = HashMap::new();
+    let mut current: Option<String> = None;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(group) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            groups.entry(group.to_string()).or_default();
+            current = Some(group.to_string());
+            continue;
+        }
+
+        let (Some(group), Some((key, value))) = (&current, line.split_once('=')) else {
+            continue;
+        };
+        if let Some(entries) = groups.get_mut(group) {
+            entries
+                .entry(key.trim().to_string())
+                .or_insert_with(|| value.trim().to_string());
+        }
+    }
+
+    groups
+}
+
+/// Looks up `key` using the spec's locale matching order, falling back to the
+/// unlocalized value.
+pub(crate) fn localized(
+    group: &HashMap<String, String>,
+    key: &str,
+    locales: &[String],
+) -> Option<String> {
+    locales
+        .iter()
+        .find_map(|locale| group.get(&format!("{}[{}]", key, locale)))
+        .or_else(|| group.get(key))
+        .map(|value| unescape(value))
+}
+
+/// The unescaped value of a string key.
+pub(crate) fn string(group: &HashMap<String, String>, key: &str) -> Option<String> {
+    group.get(key).map(|value| unescape(value))
+}
+
+/// Locale variants to try, from `lang_COUNTRY@MODIFIER` down to `lang`.
+pub(crate) fn current_locales() -> Vec<String> {
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default();
+
+    // Drop the encoding, e.g. `de_DE.UTF-8@euro` -> `de_DE@euro`
+    let (base, modifier) = match locale.split_once('@') {
+        Some((base, modifier)) => (base, Some(modifier)),
+        None => (locale.as_str(), None),
+    };
+    let base = base.split('.').next().unwrap_or_default();
+    if base.is_empty() || base == "C" || base == "POSIX" {
+        return Vec::new();
+    }
+
+    let lang = base.split('_').next().unwrap_or(base);
+    let country = base.split_once('_').map(|(_, country)| country);
+
+    let mut locales = Vec::new();
+    if let (Some(country), Some(modifier)) = (country, modifier) {
+        locales.push(format!("{}_{}@{}", lang, country, modifier));
+    }
+    if let Some(country) = country {
+        locales.push(format!("{}_{}", lang, country));
+    }
+    if let Some(modifier) = modifier {
+        locales.push(format!("{}@{}", lang, modifier));
+    }
+    locales.push(lang.to_string());
+    locales
+}
+
+pub(crate) fn current_desktops() -> Vec<String> {
+    std::env::var("XDG_CURRENT_DESKTOP")
+        .unwrap_or_default()
+        .split(':')
+        .filter(|desktop| !desktop.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn is_true(value: Option<&String>) -> bool {
+    value.is_some_and(|value| value == "true")
+}
+
+fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => result.push(' '),
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some('\\') => result.push('\\'),
+            Some(';') => result.push(';'),
+            Some(other) => {
+                result.push('\\');
+                result.push(other);
+            }
+            None => result.push('\\'),
+        }
+    }
+
+    result
+}
+
+/// Splits an escaped list value on `;`, then unescapes each item, so that
+/// `\;` stays part of an item.
+pub(crate) fn split_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            // Escape sequences are copied whole, so `\\;` still ends an item
+            '\\' => {
+                current.push(c);
+                current.extend(chars.next());
+            }
+            ';' => items.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    items.push(current);
+
+    items
+        .into_iter()
+        .map(|item| unescape(item.trim()))
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_groups_and_keys() {
+        let groups = parse_groups(
+            "# comment\n\
+             [Desktop Entry]\n\
+             Name = Firefox \n\
+             Name[de]=Firefox-Browser\n\
+             Name=Ignored duplicate\n\
+             Exec=firefox %u\n\
+             \n\
+             [Desktop Action new-window]\n\
+             Name=New Window\n",
+        );
+
+        let entry = &groups["Desktop Entry"];
+        assert_eq!(entry["Name"], "Firefox");
+        assert_eq!(entry["Name[de]"], "Firefox-Browser");
+        assert_eq!(entry["Exec"], "firefox %u");
+        assert_eq!(groups["Desktop Action new-window"]["Name"], "New Window");
+    }
+
+    #[test]
+    fn skips_keys_outside_groups_and_lines_without_values() {
+        let groups = parse_groups("Name=Orphan\n[Desktop Entry]\nNoValue\n[Empty]\n");
+        assert_eq!(groups.len(), 2);
+        assert!(groups["Desktop Entry"].is_empty());
+        assert!(groups["Empty"].is_empty());
+    }
+
+    #[test]
+    fn unescapes_values() {
+        let groups = parse_groups("[Desktop Entry]\nComment=a\\sb\\tc\\nd\\\\e\\;\n");
+        let entry = &groups["Desktop Entry"];
+        assert_eq!(string(entry, "Comment").as_deref(), Some("a b\tc\nd\\e;"));
+    }
+
+    #[test]
+    fn splits_lists_on_unescaped_semicolons() {
+        assert_eq!(
+            split_list("a;b\\;c;;d\\\\;e\\s;"),
+            ["a", "b;c", "d\\", "e "]
+        );
+        assert!(split_list("").is_empty());
+    }
+
+    fn entry(icon: Option<&str>) -> DesktopEntry {
+        DesktopEntry {
+            path: PathBuf::from("/usr/share/applications/viewer.desktop"),
+            id: "viewer.desktop".to_string(),
+            entry_type: "Application".to_string(),
+            name: "Image Viewer".to_string(),
+            generic_name: None,
+            comment: None,
+            keywords: Vec::new(),
+            icon: icon.map(str::to_string),
+            exec: None,
+            working_dir: None,
+            terminal: false,
+            no_display: false,
+            hidden: false,
+            only_show_in: Vec::new(),
+            not_show_in: Vec::new(),
+            mime_types: Vec::new(),
+            actions: Vec::new(),
+        }
+    }
+
+    fn expand(exec: &str, icon: Option<&str>, files: &[&str]) -> Vec<String> {
+        let files: Vec<String> = files.iter().map(|file| file.to_string()).collect();
+        expand_exec(exec, &entry(icon), &files).unwrap()
+    }
+
+    #[test]
+    fn splits_quoted_arguments() {
+        assert_eq!(
+            split_exec(r#"app  "two words" "a \"q\" \$HOME \` \\ \x" plain"#).unwrap(),
+            ["app", "two words", r#"a "q" $HOME ` \ \x"#, "plain"]
+        );
+        assert_eq!(split_exec(r#"app """#).unwrap(), ["app", ""]);
+        assert!(split_exec(r#"app "open"#).is_err());
+        assert!(split_exec(r#"app "open\"#).is_err());
+    }
+
+    #[test]
+    fn expands_file_codes() {
+        let files = ["/tmp/a.png", "/tmp/b.png"];
+        assert_eq!(expand("viewer %f", None, &files), ["viewer", "/tmp/a.png"]);
+        assert_eq!(expand("viewer %u", None, &files), ["viewer", "/tmp/a.png"]);
+        assert_eq!(
+            expand("viewer %F", None, &files),
+            ["viewer", "/tmp/a.png", "/tmp/b.png"]
+        );
+        assert_eq!(
+            expand("viewer %U", None, &files),
+            ["viewer", "/tmp/a.png", "/tmp/b.png"]
+        );
+        assert_eq!(expand("viewer %F", None, &[]), ["viewer"]);
+        assert_eq!(
+            expand("viewer --file=%f", None, &files),
+            ["viewer", "--file=/tmp/a.png"]
+        );
+    }
+
+    #[test]
+    fn expands_entry_codes() {
+        assert_eq!(
+            expand("viewer %i --name %c %k", Some("viewer"), &[]),
+            [
+                "viewer",
+                "--icon",
+                "viewer",
+                "--name",
+                "Image Viewer",
+                "/usr/share/applications/viewer.desktop"
+            ]
+        );
+        assert_eq!(expand("viewer %i", None, &[]), ["viewer"]);
+        assert_eq!(
+            expand("viewer --zoom=100%% %d %m", None, &[]),
+            ["viewer", "--zoom=100%"]
+        );
+        assert!(expand_exec("%f", &entry(None), &[]).is_err());
+    }
+
+    #[test]
+    fn unescapes_exec_before_splitting() {
+        let groups = parse_groups("[Desktop Entry]\nExec=sh -c \"echo \\\\\"hi\\\\\"\" a\\;b\n");
+        let exec = string(&groups["Desktop Entry"], "Exec").unwrap();
+        assert_eq!(
+            split_exec(&exec).unwrap(),
+            ["sh", "-c", "echo \"hi\"", "a;b"]
+        );
+    }
+
+    #[test]
+    fn looks_up_localized_values() {
+        let groups = parse_groups("[Desktop Entry]\nName=Files\nName[de]=Dateien\n");
+        let entry = &groups["Desktop Entry"];
+        let locales = ["de_DE".to_string(), "de".to_string()];
+        assert_eq!(
+            localized(entry, "Name", &locales).as_deref(),
+            Some("Dateien")
+        );
+        assert_eq!(localized(entry, "Name", &[]).as_deref(), Some("Files"));
+        assert_eq!(localized(entry, "Comment", &locales), None);
+    }
+}
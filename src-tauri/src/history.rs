@@ -0,0 +1,152 @@
+use crate::config::write_atomically;
+use crate::models::SearchResult;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Oldest launches are forgotten once the history grows past this
+const MAX_HISTORY_ENTRIES: usize = 2000;
+
+// Upper bound of the score added for frequently picked results
+const MAX_FRECENCY_BOOST: f32 = 0.5;
+// Frecency at which a result gets half of the maximum boost
+const HALF_BOOST_FRECENCY: f32 = 200.0;
+// Launches from a query related to the current one count this much more
+const QUERY_MATCH_MULTIPLIER: f32 = 2.0;
+
+const DAY: u64 = 24 * 60 * 60;
+
+// Launch history, loaded from disk on first use
+static HISTORY: OnceLock<RwLock<Vec<HistoryEntry>>> = OnceLock::new();
+// Snapshots are saved in the background, numbered so that one finishing late
+// never overwrites a newer one
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+static SAVED_GENERATION: OnceLock<Mutex<u64>> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub query: String,
+    pub result_id: String,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+fn get_history() -> &'static RwLock<Vec<HistoryEntry>> {
+    HISTORY.get_or_init(|| RwLock::new(load_history().unwrap_or_default()))
+}
+
+fn get_saved_generation() -> &'static Mutex<u64> {
+    SAVED_GENERATION.get_or_init(|| Mutex::new(0))
+}
+
+/// Remembers that `result_id` was picked for `query` and persists the history
+/// on a blocking thread.
+pub fn record_launch(query: &str, result_id: &str) -> Result<(), String> {
+    let entry = HistoryEntry {
+        query: query.trim().to_lowercase(),
+        result_id: result_id.to_string(),
+        timestamp: now(),
+    };
+
+    let (snapshot, generation) = {
+        let mut history = get_history().write().map_err(|e| e.to_string())?;
+        history.push(entry);
+        if history.len() > MAX_HISTORY_ENTRIES {
+            let excess = history.len() - MAX_HISTORY_ENTRIES;
+            history.drain(..excess);
+        }
+        (
+            history.clone(),
+            GENERATION.fetch_add(1, Ordering::SeqCst) + 1,
+        )
+    };
+
+    tokio::task::spawn_blocking(move || {
+        let Ok(mut saved_generation) = get_saved_generation().lock() else {
+            return;
+        };
+        if *saved_generation > generation {
+            return;
+        }
+        match save_history(&snapshot) {
+            Ok(()) => *saved_generation = generation,
+            Err(e) => eprintln!("Failed to save launch history: {}", e),
+        }
+    });
+    Ok(())
+}
+
+/// Raises the score of results that were picked before, favoring recent
+/// launches and launches from similar queries.
+pub fn apply_frecency<'a>(query: &str, results: impl IntoIterator<Item = &'a mut SearchResult>) {
+    let frecencies = frecency_scores(query);
+    if frecencies.is_empty() {
+        return;
+    }
+
+    for result in results {
+        if let Some(frecency) = frecencies.get(&result.id) {
+            result.score += MAX_FRECENCY_BOOST * frecency / (frecency + HALF_BOOST_FRECENCY);
+        }
+    }
+}
+
+fn frecency_scores(query: &str) -> HashMap<String, f32> {
+    let query = query.trim().to_lowercase();
+    let now = now();
+    let mut scores: HashMap<String, f32> = HashMap::new();
+
+    let Ok(history) = get_history().read() else {
+        return scores;
+    };
+    for entry in history.iter() {
+        let mut weight = recency_weight(now.saturating_sub(entry.timestamp));
+        // "te" should benefit from launches made by typing "term"
+        let related = entry.query.starts_with(&query) || query.starts_with(&entry.query);
+        if !query.is_empty() && !entry.query.is_empty() && related {
+            weight *= QUERY_MATCH_MULTIPLIER;
+        }
+        *scores.entry(entry.result_id.clone()).or_default() += weight;
+    }
+
+    scores
+}
+
+// Bucketed decay, similar to browser frecency
+fn recency_weight(age: u64) -> f32 {
+    match age / DAY {
+        0..=3 => 100.0,
+        4..=13 => 70.0,
+        14..=30 => 50.0,
+        31..=90 => 30.0,
+        _ => 10.0,
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+fn get_history_path() -> Result<PathBuf, String> {
+    let data_dir = dirs::data_dir()
+        .ok_or("Could not find data directory")?
+        .join("lumina");
+    Ok(data_dir.join("history.json"))
+}
+
+fn load_history() -> Result<Vec<HistoryEntry>, String> {
+    let history_content =
+        std::fs::read_to_string(get_history_path()?).map_err(|e| e.to_string())?;
+    serde_json::from_str(&history_content).map_err(|e| e.to_string())
+}
+
+fn save_history(history: &[HistoryEntry]) -> Result<(), String> {
+    let history_json = serde_json::to_string(history).map_err(|e| e.to_string())?;
+    write_atomically(&get_history_path()?, history_json)
+}
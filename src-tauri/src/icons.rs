@@ -0,0 +1,322 @@
+use crate::desktop_entry::parse_groups;
+use crate::mime;
+use base64::Engine;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+// Icons are rendered at roughly this size in the result list
+const ICON_SIZE: u32 = 48;
+const ICON_SCALE: u32 = 1;
+// Lookup order within a directory; XPM is skipped since the webview can't show it
+const ICON_EXTENSIONS: [&str; 2] = ["png", "svg"];
+const FALLBACK_THEME: &str = "hicolor";
+// Used when no desktop settings name a theme, as GTK does
+const DEFAULT_THEME: &str = "Adwaita";
+
+// Theme chain (current theme, its parents, then hicolor), resolved once
+static THEMES: OnceLock<Vec<IconTheme>> = OnceLock::new();
+
+// Resolved data URIs by icon name, including misses
+static ICON_CACHE: OnceLock<Mutex<HashMap<String, Option<String>>>> = OnceLock::new();
+
+#[derive(Debug)]
+struct IconTheme {
+    // Every base directory containing this theme, e.g. `/usr/share/icons/Adwaita`
+    roots: Vec<PathBuf>,
+    directories: Vec<ThemeDirectory>,
+}
+
+#[derive(Debug)]
+struct ThemeDirectory {
+    path: String,
+    size: u32,
+    scale: u32,
+    kind: DirectoryKind,
+    min_size: u32,
+    max_size: u32,
+    threshold: u32,
+}
+
+#[derive(Debug, PartialEq)]
+enum DirectoryKind {
+    Fixed,
+    Scalable,
+    Threshold,
+}
+
+impl ThemeDirectory {
+    fn matches_size(&self, size: u32, scale: u32) -> bool {
+        if self.scale != scale {
+            return false;
+        }
+        match self.kind {
+            DirectoryKind::Fixed => self.size == size,
+            DirectoryKind::Scalable => (self.min_size..=self.max_size).contains(&size),
+            DirectoryKind::Threshold => {
+                self.size.saturating_sub(self.threshold) <= size
+                    && size <= self.size + self.threshold
+            }
+        }
+    }
+
+    fn size_distance(&self, size: u32, scale: u32) -> u32 {
+        let (min, max) = match self.kind {
+            DirectoryKind::Fixed => (self.size, self.size),
+            DirectoryKind::Scalable => (self.min_size, self.max_size),
+            DirectoryKind::Threshold => (
+                self.size.saturating_sub(self.threshold),
+                self.size + self.threshold,
+            ),
+        };
+        let wanted = size * scale;
+        (min * self.scale).saturating_sub(wanted) + wanted.saturating_sub(max * self.scale)
+    }
+}
+
+/// Resolves an application's `Icon` value, which is either an absolute path
+/// or an icon name, to a data URI.
+pub fn application_icon(icon: &str) -> Option<String> {
+    let icon = icon.trim();
+    if icon.is_empty() {
+        return None;
+    }
+    cached(icon, || {
+        if icon.starts_with('/') {
+            return data_uri(Path::new(icon));
+        }
+        find_icon(icon).and_then(|path| data_uri(&path))
+    })
+}
+
+/// Resolves the icon for a MIME type, falling back to the icons of its
+/// ancestors and then to their generic icons, e.g. `text/x-rust` ->
+/// `text-x-rust` -> `text-plain` -> `text-x-generic`.
+pub fn mime_icon(mime_type: &str) -> Option<String> {
+    cached(&format!("mime:{}", mime_type), || {
+        let hierarchy = mime::type_hierarchy(mime_type);
+        let specific = hierarchy.iter().map(|ancestor| ancestor.replace('/', "-"));
+        let generic = hierarchy.iter().map(|ancestor| {
+            format!(
+                "{}-x-generic",
+                ancestor.split('/').next().unwrap_or("unknown")
+            )
+        });
+
+        specific
+            .chain(generic)
+            .find_map(|name| find_icon(&name))
+            .and_then(|path| data_uri(&path))
+    })
+}
+
+pub fn folder_icon() -> Option<String> {
+    application_icon("folder")
+}
+
+fn cached(key: &str, resolve: impl FnOnce() -> Option<String>) -> Option<String> {
+    let cache = ICON_CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some(hit) = cache.lock().ok().and_then(|cache| cache.get(key).cloned()) {
+        return hit;
+    }
+
+    let resolved = resolve();
+    if let Ok(mut cache) = cache.lock() {
+        cache.insert(key.to_string(), resolved.clone());
+    }
+    resolved
+}
+
+/// Looks an icon name up through the theme chain, then in the pixmaps directory.
+fn find_icon(name: &str) -> Option<PathBuf> {
+    let themes = THEMES.get_or_init(load_theme_chain);
+
+    themes
+        .iter()
+        .find_map(|theme| lookup_icon(theme, name, ICON_SIZE, ICON_SCALE))
+        .or_else(|| lookup_fallback_icon(name))
+}
+
+fn lookup_icon(theme: &IconTheme, name: &str, size: u32, scale: u32) -> Option<PathBuf> {
+    let candidates = |directory: &ThemeDirectory| {
+        theme.roots.iter().find_map(|root| {
+            ICON_EXTENSIONS.iter().find_map(|extension| {
+                let path = root
+                    .join(&directory.path)
+                    .join(format!("{}.{}", name, extension));
+                path.is_file().then_some(path)
+            })
+        })
+    };
+
+    if let Some(path) = theme
+        .directories
+        .iter()
+        .filter(|directory| directory.matches_size(size, scale))
+        .find_map(candidates)
+    {
+        return Some(path);
+    }
+
+    theme
+        .directories
+        .iter()
+        .filter_map(|directory| {
+            candidates(directory).map(|path| (directory.size_distance(size, scale), path))
+        })
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, path)| path)
+}
+
+fn lookup_fallback_icon(name: &str) -> Option<PathBuf> {
+    icon_base_directories().iter().find_map(|base| {
+        ICON_EXTENSIONS.iter().find_map(|extension| {
+            let path = base.join(format!("{}.{}", name, extension));
+            path.is_file().then_some(path)
+        })
+    })
+}
+
+/// Base directories in lookup order: `~/.icons`, `$XDG_DATA_DIRS/icons`, pixmaps.
+fn icon_base_directories() -> Vec<PathBuf> {
+    let mut directories = Vec::new();
+
+    if let Some(home) = dirs::home_dir() {
+        directories.push(home.join(".icons"));
+    }
+    if let Some(data_home) = dirs::data_dir() {
+        directories.push(data_home.join("icons"));
+    }
+
+    let data_dirs = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+    for data_dir in data_dirs.split(':').filter(|dir| !dir.is_empty()) {
+        directories.push(PathBuf::from(data_dir).join("icons"));
+    }
+    directories.push(PathBuf::from("/usr/share/pixmaps"));
+
+    directories
+}
+
+fn load_theme_chain() -> Vec<IconTheme> {
+    let mut chain = Vec::new();
+    let mut visited = HashSet::new();
+
+    let theme = current_theme_name().unwrap_or_else(|| DEFAULT_THEME.to_string());
+    add_theme(&theme, &mut chain, &mut visited);
+    add_theme(FALLBACK_THEME, &mut chain, &mut visited);
+
+    chain
+}
+
+// Depth-first, so a theme's parents are searched before the next sibling
+fn add_theme(name: &str, chain: &mut Vec<IconTheme>, visited: &mut HashSet<String>) {
+    if !visited.insert(name.to_string()) {
+        return;
+    }
+
+    let roots: Vec<PathBuf> = icon_base_directories()
+        .into_iter()
+        .map(|base| base.join(name))
+        .filter(|root| root.is_dir())
+        .collect();
+    let Some(index_content) = roots
+        .iter()
+        .find_map(|root| std::fs::read_to_string(root.join("index.theme")).ok())
+    else {
+        return;
+    };
+
+    let groups = parse_groups(&index_content);
+    let Some(theme_group) = groups.get("Icon Theme") else {
+        return;
+    };
+
+    let directory_names = [
+        theme_group.get("Directories"),
+        theme_group.get("ScaledDirectories"),
+    ]
+    .into_iter()
+    .flatten()
+    .flat_map(|list| list.split(','))
+    .map(str::trim)
+    .filter(|name| !name.is_empty());
+
+    let directories = directory_names
+        .filter_map(|path| {
+            let group = groups.get(path)?;
+            let number = |key: &str| group.get(key).and_then(|value| value.parse::<u32>().ok());
+            let size = number("Size")?;
+            let kind = match group.get("Type").map(String::as_str) {
+                Some("Fixed") => DirectoryKind::Fixed,
+                Some("Scalable") => DirectoryKind::Scalable,
+                _ => DirectoryKind::Threshold,
+            };
+
+            Some(ThemeDirectory {
+                path: path.to_string(),
+                size,
+                scale: number("Scale").unwrap_or(1),
+                kind,
+                min_size: number("MinSize").unwrap_or(size),
+                max_size: number("MaxSize").unwrap_or(size),
+                threshold: number("Threshold").unwrap_or(2),
+            })
+        })
+        .collect();
+
+    let parents: Vec<String> = theme_group
+        .get("Inherits")
+        .map(|inherits| {
+            inherits
+                .split(',')
+                .map(|parent| parent.trim().to_string())
+                .filter(|parent| !parent.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    chain.push(IconTheme { roots, directories });
+    for parent in parents {
+        add_theme(&parent, chain, visited);
+    }
+}
+
+/// The user's icon theme from GTK or KDE settings.
+fn current_theme_name() -> Option<String> {
+    let config_dir = dirs::config_dir()?;
+
+    let gtk_theme = ["gtk-4.0/settings.ini", "gtk-3.0/settings.ini"]
+        .iter()
+        .filter_map(|file| std::fs::read_to_string(config_dir.join(file)).ok())
+        .find_map(|content| {
+            parse_groups(&content)
+                .get("Settings")?
+                .get("gtk-icon-theme-name")
+                .cloned()
+        });
+
+    gtk_theme
+        .or_else(|| {
+            let content = std::fs::read_to_string(config_dir.join("kdeglobals")).ok()?;
+            parse_groups(&content).get("Icons")?.get("Theme").cloned()
+        })
+        .map(|theme| theme.trim_matches('"').to_string())
+        .filter(|theme| !theme.is_empty())
+}
+
+fn data_uri(path: &Path) -> Option<String> {
+    let mime_type = match path.extension()?.to_str()? {
+        "png" => "image/png",
+        "svg" => "image/svg+xml",
+        _ => return None,
+    };
+    let bytes = std::fs::read(path).ok()?;
+    Some(format!(
+        "data:{};base64,{}",
+        mime_type,
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    ))
+}
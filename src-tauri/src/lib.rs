@@ -1,7 +1,13 @@
 // Module declarations
 pub mod actions;
 pub mod ai;
+pub mod ai_history;
 pub mod config;
+pub mod conversation;
+pub mod desktop_entry;
+pub mod history;
+pub mod icons;
+pub mod mime;
 pub mod models;
 pub mod search;
 pub mod window;
@@ -30,11 +36,19 @@ pub fn run() {
             window::hide_window,
             window::toggle_window,
             window::resize_window,
-            search::search,
+            search::start_search,
+            search::cancel_search,
+            search::index::get_index_status,
             actions::execute_action,
+            actions::execute_secondary_action,
             config::get_config,
             config::save_config,
-            ai::ai_request,
+            conversation::start_conversation,
+            conversation::continue_conversation,
+            conversation::reset_conversation,
+            conversation::cancel_ai_request,
+            ai_history::delete_ai_exchanges,
+            ai_history::clear_ai_history,
             window::set_focus_hiding_disabled
         ])
         .setup(|app| {
@@ -56,7 +70,13 @@ pub fn run() {
 
             // Setup window event handlers
             let handle = app.handle().clone();
-            setup_window_event_handlers(&window, handle);
+            setup_window_event_handlers(&window, handle.clone());
+
+            // Build the file index in the background so searches never walk the disk,
+            // and keep it current with filesystem events between refreshes
+            search::index::start_background_indexing(handle.clone());
+            search::watcher::start_file_watcher(handle);
+            search::watcher::start_application_watcher();
 
             Ok(())
         })
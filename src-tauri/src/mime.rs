@@ -0,0 +1,633 @@
+use crate::desktop_entry::{
+    current_desktops, data_home, parse_groups, split_list, system_data_dirs,
+};
+use globset::{GlobBuilder, GlobMatcher};
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::SystemTime;
+
+pub const DIRECTORY_TYPE: &str = "inode/directory";
+const TEXT_TYPE: &str = "text/plain";
+const BINARY_TYPE: &str = "application/octet-stream";
+
+// How much of a file is read for magic sniffing and the text heuristic
+const SNIFF_LENGTH: usize = 4096;
+
+// shared-mime-info database, loaded once on first use
+static DATABASE: OnceLock<MimeDatabase> = OnceLock::new();
+// Merged mimeapps.list files, reloaded whenever one of them changes
+static ASSOCIATIONS: OnceLock<RwLock<AssociationCache>> = OnceLock::new();
+
+#[derive(Default)]
+struct MimeDatabase {
+    /// Whole file names, e.g. `Makefile`
+    literals: HashMap<String, Vec<GlobRule>>,
+    /// Simple `*.ext` patterns keyed by the extension
+    extensions: HashMap<String, Vec<GlobRule>>,
+    /// Everything else, e.g. `*.[1-9]` or `README*`
+    patterns: Vec<(GlobMatcher, GlobRule)>,
+    magic: Vec<MagicSection>,
+    aliases: HashMap<String, String>,
+    parents: HashMap<String, Vec<String>>,
+}
+
+#[derive(Clone)]
+struct GlobRule {
+    weight: u32,
+    mime_type: String,
+    pattern_length: usize,
+    case_sensitive: bool,
+    pattern: String,
+}
+
+struct MagicSection {
+    priority: u32,
+    mime_type: String,
+    matches: Vec<MagicMatch>,
+}
+
+struct MagicMatch {
+    indent: usize,
+    offset: usize,
+    range: usize,
+    value: Vec<u8>,
+    mask: Option<Vec<u8>>,
+}
+
+#[derive(Default)]
+struct AssociationCache {
+    modified: Vec<Option<SystemTime>>,
+    associations: Arc<Associations>,
+}
+
+#[derive(Default)]
+struct Associations {
+    defaults: HashMap<String, Vec<String>>,
+    added: HashMap<String, Vec<String>>,
+    removed: HashMap<String, HashSet<String>>,
+}
+
+/// Determines the MIME type of a file from its name, sniffing its contents
+/// when the name is unknown or ambiguous.
+pub fn detect(path: &Path, is_dir: bool) -> String {
+    if is_dir {
+        return DIRECTORY_TYPE.to_string();
+    }
+
+    let database = database();
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let candidates = database.glob_candidates(&file_name);
+
+    if let [only] = candidates.as_slice() {
+        return only.clone();
+    }
+    // Opening a FIFO blocks, and device nodes have no content worth sniffing
+    if !std::fs::metadata(path).is_ok_and(|metadata| metadata.is_file()) {
+        return candidates
+            .first()
+            .cloned()
+            .unwrap_or_else(|| BINARY_TYPE.to_string());
+    }
+
+    let data = read_head(path);
+    let sniffed = database.sniff(&data);
+    match (candidates.first(), sniffed) {
+        // Magic settles conflicts between equally good globs
+        (Some(_), Some(sniffed)) if candidates.contains(&sniffed) => sniffed,
+        (Some(first), _) => first.clone(),
+        (None, Some(sniffed)) => sniffed,
+        (None, None) if looks_like_text(&data) => TEXT_TYPE.to_string(),
+        (None, None) => BINARY_TYPE.to_string(),
+    }
+}
+
+/// The MIME type itself followed by its ancestors, ending with the generic
+/// text or binary type. Handlers and icons fall back along this chain.
+pub fn type_hierarchy(mime_type: &str) -> Vec<String> {
+    let database = database();
+    let mut hierarchy = vec![database.canonical(mime_type)];
+    let mut i = 0;
+
+    while i < hierarchy.len() {
+        let parents = database.parents_of(&hierarchy[i]);
+        for parent in parents {
+            if !hierarchy.contains(&parent) {
+                hierarchy.push(parent);
+            }
+        }
+        i += 1;
+    }
+
+    if hierarchy[0] != DIRECTORY_TYPE && !hierarchy.iter().any(|t| t == BINARY_TYPE) {
+        hierarchy.push(BINARY_TYPE.to_string());
+    }
+    hierarchy
+}
+
+/// Desktop file IDs of the applications that can open the MIME type, the
+/// configured default first. `installed` lists the IDs and declared MIME
+/// types of the available applications.
+pub fn handlers<'a>(
+    mime_type: &str,
+    installed: impl Iterator<Item = (&'a str, &'a [String])> + Clone,
+) -> Vec<String> {
+    let associations = associations();
+    let mut handlers: Vec<String> = Vec::new();
+    let is_installed = |id: &str| {
+        installed
+            .clone()
+            .any(|(installed_id, _)| installed_id == id)
+    };
+
+    for mime_type in type_hierarchy(mime_type) {
+        // Generic binary handlers are useless for everything but unknown files
+        if mime_type == BINARY_TYPE && !handlers.is_empty() {
+            break;
+        }
+
+        let removed = associations.removed.get(&mime_type);
+        let is_removed = |id: &str| removed.is_some_and(|removed| removed.contains(id));
+
+        let configured = associations
+            .defaults
+            .get(&mime_type)
+            .into_iter()
+            .chain(associations.added.get(&mime_type))
+            .flatten()
+            .map(String::as_str);
+        let declared = installed
+            .clone()
+            .filter(|(_, types)| types.iter().any(|t| database().canonical(t) == mime_type))
+            .map(|(id, _)| id);
+
+        for id in configured.chain(declared) {
+            if !is_removed(id) && is_installed(id) && !handlers.iter().any(|h| h == id) {
+                handlers.push(id.to_string());
+            }
+        }
+    }
+
+    handlers
+}
+
+fn database() -> &'static MimeDatabase {
+    DATABASE.get_or_init(MimeDatabase::load)
+}
+
+impl MimeDatabase {
+    fn load() -> MimeDatabase {
+        let mut database = MimeDatabase::default();
+
+        // Lowest priority first so that user definitions are loaded last
+        let mut mime_dirs: Vec<PathBuf> = system_data_dirs()
+            .into_iter()
+            .rev()
+            .map(|dir| dir.join("mime"))
+            .collect();
+        if let Some(data_home) = data_home() {
+            mime_dirs.push(data_home.join("mime"));
+        }
+
+        for dir in &mime_dirs {
+            if let Ok(content) = std::fs::read_to_string(dir.join("globs2")) {
+                database.load_globs(&content);
+            }
+            if let Ok(content) = std::fs::read(dir.join("magic")) {
+                database.magic.extend(parse_magic(&content));
+            }
+            if let Ok(content) = std::fs::read_to_string(dir.join("aliases")) {
+                for (alias, canonical) in parse_pairs(&content) {
+                    database.aliases.insert(alias, canonical);
+                }
+            }
+            if let Ok(content) = std::fs::read_to_string(dir.join("subclasses")) {
+                for (child, parent) in parse_pairs(&content) {
+                    database.parents.entry(child).or_default().push(parent);
+                }
+            }
+        }
+
+        // Sections from different directories are only sorted within each file
+        database
+            .magic
+            .sort_by_key(|section| std::cmp::Reverse(section.priority));
+        database
+    }
+
+    fn load_globs(&mut self, content: &str) {
+        for line in content.lines() {
+            if line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split(':');
+            let (Some(weight), Some(mime_type), Some(pattern)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            let Ok(weight) = weight.parse::<u32>() else {
+                continue;
+            };
+            let case_sensitive = fields.next().is_some_and(|flags| flags.contains("cs"));
+
+            let rule = GlobRule {
+                weight,
+                mime_type: mime_type.to_string(),
+                pattern_length: pattern.chars().count(),
+                case_sensitive,
+                pattern: pattern.to_string(),
+            };
+
+            let is_wildcard = |c: char| matches!(c, '*' | '?' | '[');
+            if !pattern.contains(is_wildcard) {
+                self.literals
+                    .entry(pattern.to_lowercase())
+                    .or_default()
+                    .push(rule);
+            } else if let Some(extension) = pattern
+                .strip_prefix("*.")
+                .filter(|ext| !ext.contains(is_wildcard))
+            {
+                self.extensions
+                    .entry(extension.to_lowercase())
+                    .or_default()
+                    .push(rule);
+            } else if let Ok(glob) = GlobBuilder::new(pattern)
+                .case_insensitive(!case_sensitive)
+                .literal_separator(true)
+                .build()
+            {
+                self.patterns.push((glob.compile_matcher(), rule));
+            }
+        }
+    }
+
+    /// MIME types whose globs match the name with the highest weight, the
+    /// longest pattern first.
+    fn glob_candidates(&self, file_name: &str) -> Vec<String> {
+        let lower = file_name.to_lowercase();
+        let mut matched: Vec<&GlobRule> = Vec::new();
+
+        let accepts = |rule: &GlobRule, text: &str| {
+            !rule.case_sensitive || rule.pattern.trim_start_matches('*') == text
+        };
+
+        if let Some(rules) = self.literals.get(&lower) {
+            matched.extend(rules.iter().filter(|rule| accepts(rule, file_name)));
+        }
+        if matched.is_empty() {
+            // Try `tar.gz` before `gz`
+            for (i, _) in lower.match_indices('.') {
+                let Some(rules) = self.extensions.get(&lower[i + 1..]) else {
+                    continue;
+                };
+                let original = file_name.get(i..).unwrap_or_default();
+                matched.extend(rules.iter().filter(|rule| accepts(rule, original)));
+                if !matched.is_empty() {
+                    break;
+                }
+            }
+        }
+        if matched.is_empty() {
+            matched.extend(
+                self.patterns
+                    .iter()
+                    .filter(|(glob, _)| glob.is_match(file_name))
+                    .map(|(_, rule)| rule),
+            );
+        }
+
+        let Some(best_weight) = matched.iter().map(|rule| rule.weight).max() else {
+            return Vec::new();
+        };
+        matched.retain(|rule| rule.weight == best_weight);
+        matched.sort_by_key(|rule| std::cmp::Reverse(rule.pattern_length));
+
+        let mut candidates: Vec<String> = Vec::new();
+        for rule in matched {
+            let mime_type = self.canonical(&rule.mime_type);
+            if !candidates.contains(&mime_type) {
+                candidates.push(mime_type);
+            }
+        }
+        candidates
+    }
+
+    fn sniff(&self, data: &[u8]) -> Option<String> {
+        if data.is_empty() {
+            return None;
+        }
+        self.magic
+            .iter()
+            .find(|section| section_matches(&section.matches, data))
+            .map(|section| self.canonical(&section.mime_type))
+    }
+
+    fn canonical(&self, mime_type: &str) -> String {
+        self.aliases
+            .get(mime_type)
+            .cloned()
+            .unwrap_or_else(|| mime_type.to_string())
+    }
+
+    fn parents_of(&self, mime_type: &str) -> Vec<String> {
+        let mut parents = self.parents.get(mime_type).cloned().unwrap_or_default();
+        // Implicit in the spec: every text format is also plain text
+        if mime_type.starts_with("text/") && mime_type != TEXT_TYPE {
+            parents.push(TEXT_TYPE.to_string());
+        }
+        parents
+    }
+}
+
+/// Parses the binary `magic` file: `[priority:type]` section headers followed
+/// by `[indent]>offset=<u16 length><value>[&mask][~word size][+range]` lines.
+fn parse_magic(content: &[u8]) -> Vec<MagicSection> {
+    let Some(mut rest) = content.strip_prefix(b"MIME-Magic\0\n") else {
+        return Vec::new();
+    };
+    let mut sections: Vec<MagicSection> = Vec::new();
+
+    while !rest.is_empty() {
+        if rest[0] == b'[' {
+            let Some(end) = rest.iter().position(|&b| b == b'\n') else {
+                break;
+            };
+            let header = String::from_utf8_lossy(&rest[1..end]);
+            let header = header.trim_end_matches(']');
+            if let Some((priority, mime_type)) = header.split_once(':') {
+                sections.push(MagicSection {
+                    priority: priority.parse().unwrap_or(0),
+                    mime_type: mime_type.to_string(),
+                    matches: Vec::new(),
+                });
+            }
+            rest = &rest[end + 1..];
+            continue;
+        }
+
+        let Some((magic_match, remaining)) = parse_magic_line(rest) else {
+            // Skip a line we don't understand
+            match rest.iter().position(|&b| b == b'\n') {
+                Some(end) => rest = &rest[end + 1..],
+                None => break,
+            }
+            continue;
+        };
+        if let Some(section) = sections.last_mut() {
+            section.matches.push(magic_match);
+        }
+        rest = remaining;
+    }
+
+    sections
+}
+
+fn parse_magic_line(line: &[u8]) -> Option<(MagicMatch, &[u8])> {
+    let (indent, rest) = parse_number(line);
+    let rest = rest.strip_prefix(b">")?;
+    let (offset, rest) = parse_number(rest);
+    let rest = rest.strip_prefix(b"=")?;
+
+    let length = u16::from_be_bytes([*rest.first()?, *rest.get(1)?]) as usize;
+    let mut value = rest.get(2..2 + length)?.to_vec();
+    let mut rest = &rest[2 + length..];
+
+    let mut mask = None;
+    if let Some(after) = rest.strip_prefix(b"&") {
+        mask = Some(after.get(..length)?.to_vec());
+        rest = &after[length..];
+    }
+    let mut word_size = 1;
+    if let Some(after) = rest.strip_prefix(b"~") {
+        let (size, after) = parse_number(after);
+        word_size = size.unwrap_or(1);
+        rest = after;
+    }
+    let mut range = 1;
+    if let Some(after) = rest.strip_prefix(b"+") {
+        let (length, after) = parse_number(after);
+        range = length.unwrap_or(1).max(1);
+        rest = after;
+    }
+    let rest = rest.strip_prefix(b"\n")?;
+
+    // Multi-byte words are stored big-endian
+    if cfg!(target_endian = "little") && (word_size == 2 || word_size == 4) {
+        for word in value.chunks_mut(word_size) {
+            word.reverse();
+        }
+        if let Some(mask) = &mut mask {
+            for word in mask.chunks_mut(word_size) {
+                word.reverse();
+            }
+        }
+    }
+
+    Some((
+        MagicMatch {
+            indent: indent.unwrap_or(0),
+            offset: offset?,
+            range,
+            value,
+            mask,
+        },
+        rest,
+    ))
+}
+
+fn parse_number(data: &[u8]) -> (Option<usize>, &[u8]) {
+    let end = data
+        .iter()
+        .position(|&b| !b.is_ascii_digit())
+        .unwrap_or(data.len());
+    let number = std::str::from_utf8(&data[..end])
+        .ok()
+        .and_then(|digits| digits.parse().ok());
+    (number, &data[end..])
+}
+
+/// A match succeeds when any top-level rule matches along with at least one
+/// of its nested rules, if it has any.
+fn section_matches(matches: &[MagicMatch], data: &[u8]) -> bool {
+    matches_at_level(matches, 0, data)
+}
+
+fn matches_at_level(matches: &[MagicMatch], indent: usize, data: &[u8]) -> bool {
+    let mut i = 0;
+    while i < matches.len() {
+        let magic_match = &matches[i];
+        let children_end = matches[i + 1..]
+            .iter()
+            .position(|child| child.indent <= indent)
+            .map_or(matches.len(), |end| i + 1 + end);
+        let children = &matches[i + 1..children_end];
+
+        if magic_match.indent == indent
+            && magic_match.matches(data)
+            && (children.is_empty() || matches_at_level(children, indent + 1, data))
+        {
+            return true;
+        }
+        i = children_end;
+    }
+    false
+}
+
+impl MagicMatch {
+    fn matches(&self, data: &[u8]) -> bool {
+        (self.offset..self.offset + self.range).any(|start| {
+            let Some(window) = data.get(start..start + self.value.len()) else {
+                return false;
+            };
+            match &self.mask {
+                Some(mask) => window
+                    .iter()
+                    .zip(&self.value)
+                    .zip(mask)
+                    .all(|((byte, value), mask)| byte & mask == value & mask),
+                None => window == self.value.as_slice(),
+            }
+        })
+    }
+}
+
+fn read_head(path: &Path) -> Vec<u8> {
+    let mut data = Vec::new();
+    if let Ok(file) = std::fs::File::open(path) {
+        let _ = file.take(SNIFF_LENGTH as u64).read_to_end(&mut data);
+    }
+    data
+}
+
+fn looks_like_text(data: &[u8]) -> bool {
+    if data.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(data) {
+        Ok(_) => true,
+        // The read may have cut a multi-byte character in half
+        Err(error) => error.error_len().is_none(),
+    }
+}
+
+fn parse_pairs(content: &str) -> Vec<(String, String)> {
+    content
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .map(|(first, second)| (first.trim().to_string(), second.trim().to_string()))
+        .collect()
+}
+
+fn associations() -> Arc<Associations> {
+    let cache = ASSOCIATIONS.get_or_init(|| RwLock::new(AssociationCache::default()));
+    let files = mimeapps_files();
+    let modified: Vec<Option<SystemTime>> = files
+        .iter()
+        .map(|file| std::fs::metadata(file).and_then(|m| m.modified()).ok())
+        .collect();
+
+    if let Ok(current) = cache.read() {
+        if current.modified == modified {
+            return Arc::clone(&current.associations);
+        }
+    }
+
+    let associations = Arc::new(load_associations(&files));
+    if let Ok(mut current) = cache.write() {
+        *current = AssociationCache {
+            modified,
+            associations: Arc::clone(&associations),
+        };
+    }
+    associations
+}
+
+fn load_associations(files: &[PathBuf]) -> Associations {
+    let mut associations = Associations::default();
+    let database = database();
+
+    for file in files {
+        let Ok(content) = std::fs::read_to_string(file) else {
+            continue;
+        };
+        let groups = parse_groups(&content);
+
+        let entries = |group: &str| {
+            groups
+                .get(group)
+                .into_iter()
+                .flatten()
+                .map(|(mime_type, ids)| (database.canonical(mime_type), split_list(ids)))
+                .collect::<Vec<_>>()
+        };
+
+        // Files are read most important first, so earlier IDs take precedence
+        for (mime_type, ids) in entries("Default Applications") {
+            associations
+                .defaults
+                .entry(mime_type)
+                .or_default()
+                .extend(ids);
+        }
+        for (mime_type, ids) in entries("Added Associations") {
+            associations.added.entry(mime_type).or_default().extend(ids);
+        }
+        for (mime_type, ids) in entries("Removed Associations") {
+            associations
+                .removed
+                .entry(mime_type)
+                .or_default()
+                .extend(ids);
+        }
+    }
+
+    associations
+}
+
+/// `mimeapps.list` locations in precedence order, with desktop-specific
+/// variants before the generic file in each directory.
+fn mimeapps_files() -> Vec<PathBuf> {
+    let mut directories = Vec::new();
+
+    let config_home = std::env::var("XDG_CONFIG_HOME")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(dirs::config_dir);
+    directories.extend(config_home);
+    directories.extend(
+        std::env::var("XDG_CONFIG_DIRS")
+            .ok()
+            .filter(|dirs| !dirs.is_empty())
+            .unwrap_or_else(|| "/etc/xdg".to_string())
+            .split(':')
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from),
+    );
+    directories.extend(data_home().map(|dir| dir.join("applications")));
+    directories.extend(
+        system_data_dirs()
+            .into_iter()
+            .map(|dir| dir.join("applications")),
+    );
+
+    let desktops: Vec<String> = current_desktops()
+        .iter()
+        .map(|desktop| desktop.to_lowercase())
+        .collect();
+
+    let mut files = Vec::new();
+    for directory in directories {
+        for desktop in &desktops {
+            files.push(directory.join(format!("{}-mimeapps.list", desktop)));
+        }
+        files.push(directory.join("mimeapps.list"));
+    }
+    files
+}
@@ -1,12 +1,139 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    /// "openrouter", "openai", "anthropic", "gemini", "ollama" or the name of
+    /// one of `ai_providers`
     pub ai_service: String,
     pub openrouter_api_key: Option<String>,
     pub openai_api_key: Option<String>,
+    #[serde(default)]
+    pub anthropic_api_key: Option<String>,
+    #[serde(default)]
+    pub gemini_api_key: Option<String>,
+    /// Model used with OpenRouter, e.g. `anthropic/claude-3.5-sonnet`
     pub default_model: String,
-    pub search_directories: Vec<String>,
+    /// Model used with each of the other built-in services, named the way
+    /// that service expects
+    #[serde(default = "default_openai_model")]
+    pub openai_model: String,
+    #[serde(default = "default_anthropic_model")]
+    pub anthropic_model: String,
+    #[serde(default = "default_gemini_model")]
+    pub gemini_model: String,
+    #[serde(default = "default_ollama_model")]
+    pub ollama_model: String,
+    pub search_directories: Vec<SearchDirectory>,
+    /// Glob patterns skipped in every search directory
+    #[serde(default = "default_global_excludes")]
+    pub global_excludes: Vec<String>,
+    /// Command used to run `Terminal=true` applications, with the program
+    /// appended (e.g. `alacritty -e`); detected automatically when unset
+    #[serde(default)]
+    pub terminal_emulator: Option<String>,
+    /// Query prefixes that send the rest of the query to a single provider
+    #[serde(default = "default_keyword_prefixes")]
+    pub keyword_prefixes: Vec<KeywordPrefix>,
+    /// When the "Ask AI" result is offered for ordinary queries
+    #[serde(default)]
+    pub ai_detection: AiDetection,
+    /// Endpoint serving the exchange rates used for currency conversion
+    #[serde(default = "default_exchange_rates_url")]
+    pub exchange_rates_url: String,
+    /// Approximate number of tokens of conversation history sent to the AI;
+    /// the oldest turns are dropped beyond it
+    #[serde(default = "default_ai_context_tokens")]
+    pub ai_context_tokens: usize,
+    /// Address of the Ollama server used when `ai_service` is "ollama"
+    #[serde(default = "default_ollama_url")]
+    pub ollama_url: String,
+    /// OpenAI-compatible endpoints, selected by name through `ai_service`
+    #[serde(default)]
+    pub ai_providers: Vec<AiProviderConfig>,
+}
+
+/// A chat completions API that speaks the OpenAI protocol, such as a local
+/// llama.cpp or vLLM server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AiProviderConfig {
+    pub name: String,
+    /// URL that `/chat/completions` is appended to, e.g. `http://localhost:8080/v1`
+    pub base_url: String,
+    /// Sent as a bearer token when set
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Extra headers sent with every request
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Model requested from the endpoint; required, since names differ per server
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AiDetection {
+    #[serde(default)]
+    pub mode: AiQueryMode,
+    /// Languages whose built-in question words are recognized, e.g. `["en", "de"]`
+    #[serde(default = "default_ai_languages")]
+    pub languages: Vec<String>,
+    /// Extra words or phrases that mark a query as a question when it starts with them
+    #[serde(default)]
+    pub trigger_words: Vec<String>,
+}
+
+impl Default for AiDetection {
+    fn default() -> Self {
+        Self {
+            mode: AiQueryMode::default(),
+            languages: default_ai_languages(),
+            trigger_words: Vec::new(),
+        }
+    }
+}
+
+fn default_ai_languages() -> Vec<String> {
+    vec!["en".to_string()]
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum AiQueryMode {
+    /// Offered for queries that look like questions, and when nothing else matched
+    #[default]
+    Auto,
+    /// Offered only when nothing else matched
+    Fallback,
+    /// Offered only through the AI keyword prefix
+    Explicit,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeywordPrefix {
+    /// Typed at the start of the query, usually ending in a space
+    pub prefix: String,
+    /// Name of the search provider, e.g. `files` or `calculator`
+    pub provider: String,
+}
+
+impl KeywordPrefix {
+    pub fn new(prefix: &str, provider: &str) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            provider: provider.to_string(),
+        }
+    }
+}
+
+fn default_keyword_prefixes() -> Vec<KeywordPrefix> {
+    vec![
+        KeywordPrefix::new("f ", "files"),
+        KeywordPrefix::new("= ", "calculator"),
+        KeywordPrefix::new("ai ", "ai"),
+        KeywordPrefix::new("> ", "shell"),
+        KeywordPrefix::new("past ", "answers"),
+    ]
 }
 
 impl Default for Config {
@@ -15,14 +142,145 @@ impl Default for Config {
             ai_service: "openrouter".to_string(),
             openrouter_api_key: None,
             openai_api_key: None,
+            anthropic_api_key: None,
+            gemini_api_key: None,
             default_model: "anthropic/claude-3.5-sonnet".to_string(),
-            search_directories: vec![
-                dirs::home_dir()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .to_string(),
-                "/usr/share/applications".to_string(),
-            ],
+            openai_model: default_openai_model(),
+            anthropic_model: default_anthropic_model(),
+            gemini_model: default_gemini_model(),
+            ollama_model: default_ollama_model(),
+            // Applications are found through the XDG data directories instead
+            search_directories: vec![SearchDirectory::new("~", default_max_depth())],
+            global_excludes: default_global_excludes(),
+            terminal_emulator: None,
+            keyword_prefixes: default_keyword_prefixes(),
+            ai_detection: AiDetection::default(),
+            exchange_rates_url: default_exchange_rates_url(),
+            ai_context_tokens: default_ai_context_tokens(),
+            ollama_url: default_ollama_url(),
+            ai_providers: Vec::new(),
+        }
+    }
+}
+
+fn default_openai_model() -> String {
+    "gpt-4o".to_string()
+}
+
+fn default_anthropic_model() -> String {
+    "claude-3-5-sonnet-latest".to_string()
+}
+
+fn default_gemini_model() -> String {
+    "gemini-1.5-pro".to_string()
+}
+
+fn default_ollama_model() -> String {
+    "llama3.2".to_string()
+}
+
+fn default_ollama_url() -> String {
+    "http://localhost:11434".to_string()
+}
+
+fn default_ai_context_tokens() -> usize {
+    8000
+}
+
+fn default_exchange_rates_url() -> String {
+    "https://api.frankfurter.app/latest".to_string()
+}
+
+fn default_global_excludes() -> Vec<String> {
+    [
+        ".git",
+        ".hg",
+        ".svn",
+        "node_modules",
+        "target",
+        "__pycache__",
+        ".venv",
+        ".cache",
+    ]
+    .iter()
+    .map(|pattern| pattern.to_string())
+    .collect()
+}
+
+/// A directory covered by file search, with its own traversal rules.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "SearchDirectoryEntry")]
+pub struct SearchDirectory {
+    /// Absolute path, or relative to the home directory when starting with `~`
+    pub path: String,
+    pub max_depth: usize,
+    /// Glob patterns a file must match to be searchable; empty means everything
+    pub include: Vec<String>,
+    /// Glob patterns for files and directories to skip entirely
+    pub exclude: Vec<String>,
+    pub include_hidden: bool,
+}
+
+impl SearchDirectory {
+    pub fn new(path: &str, max_depth: usize) -> Self {
+        Self {
+            path: path.to_string(),
+            max_depth,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            include_hidden: false,
+        }
+    }
+
+    pub fn expanded_path(&self) -> PathBuf {
+        match self.path.strip_prefix('~') {
+            Some(rest) => dirs::home_dir()
+                .unwrap_or_else(|| PathBuf::from("/"))
+                .join(rest.trim_start_matches('/')),
+            None => PathBuf::from(&self.path),
+        }
+    }
+}
+
+// Older configs stored search directories as plain path strings
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SearchDirectoryEntry {
+    Path(String),
+    Rules {
+        path: String,
+        #[serde(default = "default_max_depth")]
+        max_depth: usize,
+        #[serde(default)]
+        include: Vec<String>,
+        #[serde(default)]
+        exclude: Vec<String>,
+        #[serde(default)]
+        include_hidden: bool,
+    },
+}
+
+fn default_max_depth() -> usize {
+    3
+}
+
+impl From<SearchDirectoryEntry> for SearchDirectory {
+    fn from(entry: SearchDirectoryEntry) -> Self {
+        match entry {
+            SearchDirectoryEntry::Path(path) => SearchDirectory::new(&path, default_max_depth()),
+            SearchDirectoryEntry::Rules {
+                path,
+                max_depth,
+                include,
+                exclude,
+                include_hidden,
+            } => SearchDirectory {
+                path,
+                max_depth,
+                include,
+                exclude,
+                include_hidden,
+            },
         }
     }
 }
@@ -36,6 +294,30 @@ pub struct SearchResult {
     pub action_type: ActionType,
     pub action_data: String,
     pub score: f32,
+    /// Parts of `title` that matched the query, for highlighting
+    #[serde(default)]
+    pub matched_ranges: Vec<MatchRange>,
+    /// Alternative actions offered for this result, e.g. "New Window"
+    #[serde(default)]
+    pub secondary_actions: Vec<SecondaryAction>,
+    /// Describes the primary action, e.g. "Open with Text Editor"
+    #[serde(default)]
+    pub action_label: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecondaryAction {
+    pub id: String,
+    pub title: String,
+    pub action_type: ActionType,
+    pub action_data: String,
+}
+
+/// Half-open range of character (not byte) offsets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchRange {
+    pub start: usize,
+    pub end: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,7 +326,45 @@ pub enum ActionType {
     OpenApp,
     OpenUrl,
     CopyToClipboard,
+    /// Copies a calculator result and makes it available as `ans`
+    CopyCalculation,
     AiResponse,
+    /// Runs a `[Desktop Action ...]`; `action_data` is `<desktop file>#<action id>`
+    DesktopAction,
+    /// Opens a file in a specific application; `action_data` is `<desktop file>#<file path>`
+    OpenWith,
+    /// Runs `action_data` with `sh -c` in the background
+    RunCommand,
+    /// Runs `action_data` with `sh -c` in a terminal emulator
+    RunInTerminal,
+    /// Informational result without an action
+    Hint,
+    /// Continues a saved AI conversation; `action_data` is the id of an exchange in it
+    ReopenConversation,
+    /// Deletes saved AI exchanges; `action_data` is their comma-separated ids
+    DeleteAiExchange,
+}
+
+/// Payload of the `search_results` event: the ranked results found so far
+/// for the query numbered `generation` by the frontend.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchUpdate {
+    pub generation: u64,
+    pub results: Vec<SearchResult>,
+    /// Set on the last update for the query
+    pub done: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexStatus {
+    pub state: IndexState,
+    pub indexed_entries: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum IndexState {
+    Indexing,
+    Ready,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -54,15 +374,27 @@ pub struct OpenRouterRequest {
     pub stream: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
     pub role: String,
     pub content: String,
 }
 
+impl Message {
+    pub fn new(role: &str, content: impl Into<String>) -> Self {
+        Self {
+            role: role.to_string(),
+            content: content.into(),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct OpenRouterResponse {
+    #[serde(default)]
     pub choices: Vec<Choice>,
+    /// Set instead of choices when the request fails mid-stream
+    pub error: Option<ApiError>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -76,3 +408,77 @@ pub struct Choice {
 pub struct Delta {
     pub content: Option<String>,
 }
+
+/// Request body of the Anthropic Messages API.
+#[derive(Debug, Serialize)]
+pub struct AnthropicRequest {
+    pub model: String,
+    pub max_tokens: u32,
+    pub system: String,
+    pub messages: Vec<Message>,
+    pub stream: bool,
+}
+
+/// Data of one server-sent event of a streamed Anthropic reply.
+#[derive(Debug, Deserialize)]
+pub struct AnthropicEvent {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub delta: Option<AnthropicDelta>,
+    pub error: Option<ApiError>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AnthropicDelta {
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApiError {
+    pub message: String,
+}
+
+/// Request body of Gemini's `streamGenerateContent`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeminiRequest {
+    pub system_instruction: GeminiContent,
+    pub contents: Vec<GeminiContent>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GeminiContent {
+    /// "user" or "model"; unset for the system instruction
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    #[serde(default)]
+    pub parts: Vec<GeminiPart>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GeminiPart {
+    #[serde(default)]
+    pub text: String,
+}
+
+/// Data of one server-sent event of a streamed Gemini reply.
+#[derive(Debug, Deserialize)]
+pub struct GeminiResponse {
+    #[serde(default)]
+    pub candidates: Vec<GeminiCandidate>,
+    pub error: Option<ApiError>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GeminiCandidate {
+    pub content: Option<GeminiContent>,
+}
+
+/// One line of a streamed Ollama `/api/chat` reply.
+#[derive(Debug, Deserialize)]
+pub struct OllamaResponse {
+    pub message: Option<Message>,
+    #[serde(default)]
+    pub done: bool,
+    pub error: Option<String>,
+}
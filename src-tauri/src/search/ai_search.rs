@@ -1,15 +1,152 @@
-use crate::models::{ActionType, SearchResult};
-
-pub fn is_ai_query(query: &str) -> bool {
-    let ai_indicators = [
-        "what", "how", "why", "when", "where", "who", 
-        "explain", "tell me", "help", "?",
-    ];
-    let query_lower = query.to_lowercase();
-    ai_indicators
+use crate::models::{ActionType, AiDetection, AiQueryMode, Config, SearchResult};
+
+use super::provider::{Cancellation, SearchProvider};
+
+/// Offers to ask the AI for question-like queries, and for any query that
+/// nothing else matched, depending on the configured `AiQueryMode`.
+pub struct AiProvider;
+
+#[async_trait::async_trait]
+impl SearchProvider for AiProvider {
+    fn name(&self) -> &'static str {
+        "ai"
+    }
+
+    fn title(&self) -> &'static str {
+        "Ask AI"
+    }
+
+    fn priority(&self) -> i32 {
+        10
+    }
+
+    fn handles(&self, query: &str, config: &Config) -> bool {
+        config.ai_detection.mode == AiQueryMode::Auto && is_ai_query(query, &config.ai_detection)
+    }
+
+    fn is_fallback(&self, config: &Config) -> bool {
+        config.ai_detection.mode != AiQueryMode::Explicit
+    }
+
+    async fn query(
+        &self,
+        query: &str,
+        _cancellation: &Cancellation,
+    ) -> Result<Vec<SearchResult>, String> {
+        create_ai_search_result(query)
+            .await
+            .map(|result| vec![result])
+    }
+}
+
+// Words and phrases that open a question, per language. They only count at
+// the start of a query, as whole words.
+const QUESTION_WORDS: [(&str, &[&str]); 4] = [
+    (
+        "en",
+        &[
+            "what",
+            "what's",
+            "how",
+            "how's",
+            "why",
+            "when",
+            "where",
+            "where's",
+            "who",
+            "who's",
+            "whom",
+            "whose",
+            "which",
+            "explain",
+            "describe",
+            "define",
+            "tell me",
+            "help me",
+            "can you",
+            "could you",
+            "should i",
+            "is it",
+            "is there",
+            "are there",
+            "does",
+            "do i",
+        ],
+    ),
+    (
+        "de",
+        &[
+            "was",
+            "wie",
+            "warum",
+            "wieso",
+            "weshalb",
+            "wann",
+            "wo",
+            "wer",
+            "welche",
+            "welcher",
+            "welches",
+            "erkläre",
+            "erklär",
+            "kannst du",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            "quoi",
+            "comment",
+            "pourquoi",
+            "quand",
+            "où",
+            "qui",
+            "quel",
+            "quelle",
+            "explique",
+            "est-ce que",
+        ],
+    ),
+    (
+        "es",
+        &[
+            "qué", "cómo", "por qué", "cuándo", "dónde", "quién", "cuál", "explica",
+        ],
+    ),
+];
+
+/// Whether the query reads like a question for the AI rather than a search
+/// for a file or application.
+pub fn is_ai_query(query: &str, detection: &AiDetection) -> bool {
+    let query = query.trim().to_lowercase();
+
+    // An explicit question mark, but not a lone `?` or one ending a glob or path
+    if let Some(question) = query.strip_suffix('?') {
+        let last_word = question.split_whitespace().last().unwrap_or_default();
+        if !last_word.is_empty() && last_word.chars().all(|c| c.is_alphabetic() || c == '\'') {
+            return true;
+        }
+    }
+
+    let words: Vec<&str> = query.split_whitespace().map(trim_punctuation).collect();
+    let builtin = QUESTION_WORDS
         .iter()
-        .any(|&indicator| query_lower.contains(indicator))
-        || query.ends_with('?')
+        .filter(|(language, _)| detection.languages.iter().any(|l| l == language))
+        .flat_map(|(_, phrases)| phrases.iter().map(|phrase| phrase.to_string()));
+    let configured = detection
+        .trigger_words
+        .iter()
+        .map(|phrase| phrase.trim().to_lowercase());
+
+    builtin.chain(configured).any(|phrase| {
+        let phrase: Vec<&str> = phrase.split_whitespace().collect();
+        // A question word on its own is more likely a search for that word
+        !phrase.is_empty() && words.len() > phrase.len() && words.starts_with(&phrase)
+    })
+}
+
+fn trim_punctuation(word: &str) -> &str {
+    word.trim_matches(|c: char| !c.is_alphanumeric() && c != '\'' && c != '-')
 }
 
 pub async fn create_ai_search_result(query: &str) -> Result<SearchResult, String> {
@@ -21,5 +158,78 @@ pub async fn create_ai_search_result(query: &str) -> Result<SearchResult, String
         action_type: ActionType::AiResponse,
         action_data: query.to_string(),
         score: 0.7,
+        matched_ranges: Vec::new(),
+        secondary_actions: Vec::new(),
+        action_label: None,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detection(languages: &[&str], trigger_words: &[&str]) -> AiDetection {
+        AiDetection {
+            mode: AiQueryMode::Auto,
+            languages: languages.iter().map(|l| l.to_string()).collect(),
+            trigger_words: trigger_words.iter().map(|w| w.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn ignores_words_containing_question_words() {
+        let detection = AiDetection::default();
+        for query in [
+            "showcase",
+            "whoami",
+            "helpers.rs",
+            "whenever.txt",
+            "somehow",
+            "rewhat",
+        ] {
+            assert!(!is_ai_query(query, &detection), "{query}");
+        }
+    }
+
+    #[test]
+    fn ignores_question_words_on_their_own_or_later_in_the_query() {
+        let detection = AiDetection::default();
+        for query in [
+            "how", "where", "git help", "man who", "what.md", "file?.rs", "?",
+        ] {
+            assert!(!is_ai_query(query, &detection), "{query}");
+        }
+    }
+
+    #[test]
+    fn detects_questions() {
+        let detection = AiDetection::default();
+        for query in [
+            "how do I exit vim",
+            "What is rust",
+            "what's the capital of France",
+            "tell me a joke",
+            "is it going to rain?",
+            "weather tomorrow?",
+        ] {
+            assert!(is_ai_query(query, &detection), "{query}");
+        }
+    }
+
+    #[test]
+    fn uses_only_configured_languages() {
+        assert!(!is_ai_query("wie spät ist es", &AiDetection::default()));
+        assert!(is_ai_query(
+            "wie spät ist es",
+            &detection(&["en", "de"], &[])
+        ));
+    }
+
+    #[test]
+    fn uses_configured_trigger_words() {
+        let detection = detection(&["en"], &["summarize", "Translate into"]);
+        assert!(is_ai_query("summarize this article", &detection));
+        assert!(is_ai_query("translate into german: hello", &detection));
+        assert!(!is_ai_query("summarize", &detection));
+    }
+}
@@ -0,0 +1,151 @@
+use crate::ai_history::{self, AiExchange};
+use crate::models::{ActionType, Config, SearchResult, SecondaryAction};
+use chrono::{Local, TimeZone};
+
+use super::fuzzy::fuzzy_match;
+use super::provider::{Cancellation, SearchProvider};
+
+// Past answers rank below launchable results that match as well
+const QUESTION_WEIGHT: f32 = 0.8;
+const ANSWER_WEIGHT: f32 = 0.5;
+// Loose subsequence matches of long questions are noise
+const MIN_SCORE: f32 = 0.3;
+// Shorter queries would match most answers
+const MIN_QUERY_LENGTH: usize = 3;
+const MAX_ANSWERS: usize = 5;
+// Recent exchanges listed when the keyword prefix is typed on its own
+const RECENT_ANSWERS: usize = 10;
+const SNIPPET_LENGTH: usize = 100;
+
+/// Finds earlier AI answers by their question or answer text.
+pub struct AnswersProvider;
+
+#[async_trait::async_trait]
+impl SearchProvider for AnswersProvider {
+    fn name(&self) -> &'static str {
+        "answers"
+    }
+
+    fn title(&self) -> &'static str {
+        "Past answers"
+    }
+
+    fn priority(&self) -> i32 {
+        5
+    }
+
+    fn handles(&self, query: &str, _config: &Config) -> bool {
+        query.trim().chars().count() >= MIN_QUERY_LENGTH
+    }
+
+    async fn query(
+        &self,
+        query: &str,
+        _cancellation: &Cancellation,
+    ) -> Result<Vec<SearchResult>, String> {
+        Ok(search_answers(query))
+    }
+}
+
+pub fn search_answers(query: &str) -> Vec<SearchResult> {
+    let mut results = ai_history::with_exchanges(|exchanges| find_answers(exchanges, query.trim()));
+
+    // Offer to delete everything listed at once
+    if results.len() > 1 {
+        let ids: Vec<&str> = results
+            .iter()
+            .map(|result| result.action_data.as_str())
+            .collect();
+        let delete_all = SecondaryAction {
+            id: "delete_all".to_string(),
+            title: format!("Delete all {} listed answers", ids.len()),
+            action_type: ActionType::DeleteAiExchange,
+            action_data: ids.join(","),
+        };
+        for result in &mut results {
+            result.secondary_actions.push(delete_all.clone());
+        }
+    }
+    results
+}
+
+fn find_answers(exchanges: &[AiExchange], query: &str) -> Vec<SearchResult> {
+    if query.is_empty() {
+        return exchanges
+            .iter()
+            .rev()
+            .take(RECENT_ANSWERS)
+            .enumerate()
+            .map(|(i, exchange)| answer_result(exchange, 0.5 - i as f32 * 0.01))
+            .collect();
+    }
+
+    let lower_query = query.to_lowercase();
+    let mut results: Vec<SearchResult> = exchanges
+        .iter()
+        .rev()
+        .filter_map(|exchange| {
+            let question_score = fuzzy_match(query, &exchange.question)
+                .map(|matched| matched.score * QUESTION_WEIGHT);
+            let answer_score = exchange
+                .answer_contains(&lower_query)
+                .then_some(ANSWER_WEIGHT);
+            let score = question_score
+                .into_iter()
+                .chain(answer_score)
+                .reduce(f32::max)
+                .filter(|score| *score >= MIN_SCORE)?;
+            Some(answer_result(exchange, score))
+        })
+        .collect();
+
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    results.truncate(MAX_ANSWERS);
+    results
+}
+
+fn answer_result(exchange: &AiExchange, score: f32) -> SearchResult {
+    let date = Local
+        .timestamp_opt(exchange.timestamp as i64, 0)
+        .single()
+        .map(|time| time.format("%Y-%m-%d").to_string())
+        .unwrap_or_default();
+    let snippet: String = exchange
+        .answer
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(SNIPPET_LENGTH)
+        .collect();
+
+    SearchResult {
+        id: format!("answer_{}", exchange.id),
+        title: exchange.question.clone(),
+        description: format!("{} · {}", date, snippet),
+        icon: Some("💬".to_string()),
+        action_type: ActionType::ReopenConversation,
+        action_data: exchange.id.clone(),
+        score,
+        matched_ranges: Vec::new(),
+        secondary_actions: vec![
+            SecondaryAction {
+                id: "copy".to_string(),
+                title: "Copy answer".to_string(),
+                action_type: ActionType::CopyToClipboard,
+                action_data: exchange.answer.clone(),
+            },
+            SecondaryAction {
+                id: "delete".to_string(),
+                title: "Delete from history".to_string(),
+                action_type: ActionType::DeleteAiExchange,
+                action_data: exchange.id.clone(),
+            },
+        ],
+        action_label: Some("Continue conversation".to_string()),
+    }
+}
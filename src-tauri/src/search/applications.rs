@@ -0,0 +1,233 @@
+use crate::desktop_entry::{application_directories, DesktopEntry};
+use crate::icons::application_icon;
+use crate::models::{ActionType, SearchResult, SecondaryAction};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+use walkdir::WalkDir;
+
+use super::fuzzy::fuzzy_match;
+use super::provider::{Cancellation, SearchProvider};
+
+// How much a match on each field counts relative to a match on the name
+const GENERIC_NAME_WEIGHT: f32 = 0.9;
+const KEYWORD_WEIGHT: f32 = 0.85;
+const FILE_NAME_WEIGHT: f32 = 0.8;
+const COMMENT_WEIGHT: f32 = 0.6;
+
+// Without a running application watcher the list is reloaded this often instead
+const RELOAD_INTERVAL: Duration = Duration::from_secs(60);
+
+// Installed applications, reloaded after the watcher reports a change
+static APPLICATIONS: OnceLock<RwLock<ApplicationCache>> = OnceLock::new();
+static APPLICATIONS_CHANGED: AtomicBool = AtomicBool::new(true);
+static WATCHING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Default)]
+struct ApplicationCache {
+    loaded_at: Option<Instant>,
+    entries: Arc<Vec<DesktopEntry>>,
+}
+
+pub struct ApplicationsProvider;
+
+#[async_trait::async_trait]
+impl SearchProvider for ApplicationsProvider {
+    fn name(&self) -> &'static str {
+        "applications"
+    }
+
+    fn title(&self) -> &'static str {
+        "Applications"
+    }
+
+    fn priority(&self) -> i32 {
+        30
+    }
+
+    async fn query(
+        &self,
+        query: &str,
+        _cancellation: &Cancellation,
+    ) -> Result<Vec<SearchResult>, String> {
+        search_applications(query).await
+    }
+}
+
+pub async fn search_applications(query: &str) -> Result<Vec<SearchResult>, String> {
+    let applications = get_applications();
+
+    let results = applications
+        .iter()
+        .filter(|entry| entry.should_show())
+        .filter_map(|entry| {
+            let (score, matched_ranges) = score_application(entry, query)?;
+            Some(SearchResult {
+                id: format!("app_{}", entry.id),
+                title: entry.name.clone(),
+                description: entry
+                    .comment
+                    .clone()
+                    .or_else(|| entry.generic_name.clone())
+                    .unwrap_or_else(|| "Application".to_string()),
+                icon: entry
+                    .icon
+                    .as_deref()
+                    .and_then(application_icon)
+                    .or_else(|| Some("🚀".to_string())),
+                action_type: ActionType::OpenApp,
+                action_data: entry.path.to_string_lossy().to_string(),
+                score,
+                matched_ranges,
+                secondary_actions: application_actions(entry),
+                action_label: None,
+            })
+        })
+        .collect();
+
+    Ok(results)
+}
+
+/// Best weighted match over the entry's searchable fields. Highlight ranges
+/// are only returned for matches on the name, which is what the UI shows.
+fn score_application(
+    entry: &DesktopEntry,
+    query: &str,
+) -> Option<(f32, Vec<crate::models::MatchRange>)> {
+    let mut best = fuzzy_match(query, &entry.name).map(|matched| (matched.score, matched.ranges));
+
+    let file_name = entry.id.trim_end_matches(".desktop");
+    let other_fields = entry
+        .generic_name
+        .iter()
+        .map(|name| (name.as_str(), GENERIC_NAME_WEIGHT))
+        .chain(
+            entry
+                .keywords
+                .iter()
+                .map(|keyword| (keyword.as_str(), KEYWORD_WEIGHT)),
+        )
+        .chain(std::iter::once((file_name, FILE_NAME_WEIGHT)))
+        .chain(
+            entry
+                .comment
+                .iter()
+                .map(|comment| (comment.as_str(), COMMENT_WEIGHT)),
+        );
+
+    for (field, weight) in other_fields {
+        if let Some(matched) = fuzzy_match(query, field) {
+            let score = matched.score * weight;
+            if best
+                .as_ref()
+                .is_none_or(|(best_score, _)| score > *best_score)
+            {
+                best = Some((score, Vec::new()));
+            }
+        }
+    }
+
+    best.filter(|(score, _)| *score > 0.1)
+}
+
+fn application_actions(entry: &DesktopEntry) -> Vec<SecondaryAction> {
+    let desktop_file = entry.path.to_string_lossy();
+
+    let mut actions: Vec<SecondaryAction> = entry
+        .actions
+        .iter()
+        .filter(|action| action.exec.is_some())
+        .map(|action| SecondaryAction {
+            id: format!("desktop_action_{}", action.id),
+            title: action.name.clone(),
+            action_type: ActionType::DesktopAction,
+            action_data: format!("{}#{}", desktop_file, action.id),
+        })
+        .collect();
+
+    if let Some(parent) = entry.path.parent() {
+        actions.push(SecondaryAction {
+            id: "open_folder".to_string(),
+            title: "Open containing folder".to_string(),
+            action_type: ActionType::OpenFile,
+            action_data: parent.to_string_lossy().to_string(),
+        });
+    }
+
+    actions
+}
+
+/// Every installed application, including those hidden from menus, which
+/// can still act as file handlers.
+pub(crate) fn get_applications() -> Arc<Vec<DesktopEntry>> {
+    let cache = APPLICATIONS.get_or_init(|| RwLock::new(ApplicationCache::default()));
+
+    if let Ok(current) = cache.read() {
+        let outdated = APPLICATIONS_CHANGED.load(Ordering::SeqCst)
+            || current.loaded_at.is_none_or(|loaded_at| {
+                !WATCHING.load(Ordering::SeqCst) && loaded_at.elapsed() >= RELOAD_INTERVAL
+            });
+        if !outdated {
+            return Arc::clone(&current.entries);
+        }
+    }
+
+    // Cleared before loading, so that a change during the load triggers another
+    APPLICATIONS_CHANGED.store(false, Ordering::SeqCst);
+    let entries = Arc::new(load_applications(&application_directories()));
+    if let Ok(mut current) = cache.write() {
+        *current = ApplicationCache {
+            loaded_at: Some(Instant::now()),
+            entries: Arc::clone(&entries),
+        };
+    }
+    entries
+}
+
+/// Makes the next lookup reload the installed applications.
+pub(crate) fn invalidate_applications() {
+    APPLICATIONS_CHANGED.store(true, Ordering::SeqCst);
+}
+
+/// Whether the application directories are watched for changes; when not,
+/// the list is reloaded periodically.
+pub(crate) fn set_watching(watching: bool) {
+    WATCHING.store(watching, Ordering::SeqCst);
+}
+
+fn load_applications(directories: &[PathBuf]) -> Vec<DesktopEntry> {
+    let mut seen_ids = HashSet::new();
+    let mut applications = Vec::new();
+
+    for dir in directories {
+        let desktop_files = WalkDir::new(dir)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().extension().is_some_and(|ext| ext == "desktop"));
+
+        for file in desktop_files {
+            let Some(id) = desktop_file_id(dir, file.path()) else {
+                continue;
+            };
+            // Earlier directories override later ones, even when the override is hidden
+            if !seen_ids.insert(id.clone()) {
+                continue;
+            }
+            if let Some(entry) = DesktopEntry::parse(file.path(), id) {
+                if entry.entry_type == "Application" && !entry.hidden {
+                    applications.push(entry);
+                }
+            }
+        }
+    }
+
+    applications
+}
+
+// The ID is the path relative to the applications directory with `/` replaced by `-`
+fn desktop_file_id(dir: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(dir).ok()?.to_str()?;
+    Some(relative.replace('/', "-"))
+}
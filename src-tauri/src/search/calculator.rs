@@ -1,35 +1,661 @@
 use crate::models::{ActionType, SearchResult};
-use evalexpr::eval;
+use bigdecimal::num_bigint::BigInt;
+use bigdecimal::{BigDecimal, One, Signed, ToPrimitive, Zero};
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use super::provider::{Cancellation, SearchProvider};
+
+// Decimal places shown for results that aren't integers
+const DISPLAY_DECIMALS: i64 = 15;
+// Integers with more digits than this are shown in scientific notation
+const MAX_PLAIN_DIGITS: i64 = 40;
+// Bounds that keep exact arithmetic from running away
+const MAX_EXPONENT: i64 = 10_000;
+const MAX_FACTORIAL: u64 = 1_000;
+// Exact values never have more digits than this, or a larger scale
+const MAX_DIGITS: u64 = 20_000;
+const MAX_SCALE: u64 = 10_000;
+// About ln(10^MAX_SCALE)
+const MAX_EXP_ARGUMENT: i64 = 23_000;
+
+// The result last copied from the calculator, available as `ans`
+static LAST_ANSWER: Mutex<Option<BigDecimal>> = Mutex::new(None);
+
+pub struct CalculatorProvider;
+
+#[async_trait::async_trait]
+impl SearchProvider for CalculatorProvider {
+    fn name(&self) -> &'static str {
+        "calculator"
+    }
+
+    fn title(&self) -> &'static str {
+        "Calculator"
+    }
+
+    fn priority(&self) -> i32 {
+        40
+    }
+
+    async fn query(
+        &self,
+        query: &str,
+        _cancellation: &Cancellation,
+    ) -> Result<Vec<SearchResult>, String> {
+        calculate(query).await.map(|result| vec![result])
+    }
+}
 
 pub async fn calculate(query: &str) -> Result<SearchResult, String> {
-    // Check if the query looks like a math expression
-    let math_chars = [
-        '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', 
-        '+', '-', '*', '/', '(', ')', '.', ' ',
-    ];
-
-    if is_math_expression(query, &math_chars) {
-        match eval(query) {
-            Ok(result) => {
-                let result_str = result.to_string();
-                Ok(SearchResult {
-                    id: "calculator".to_string(),
-                    title: format!("{} = {}", query, result_str),
-                    description: "Press Enter to copy result to clipboard".to_string(),
-                    icon: Some("🧮".to_string()),
-                    action_type: ActionType::CopyToClipboard,
-                    action_data: result_str,
-                    score: 0.9,
-                })
-            }
-            Err(_) => Err("Invalid math expression".to_string()),
-        }
-    } else {
-        Err("Not a math expression".to_string())
-    }
-}
-
-fn is_math_expression(query: &str, math_chars: &[char]) -> bool {
-    query.chars().all(|c| math_chars.contains(&c)) && 
-    query.chars().any(|c| "+-*/".contains(c))
+    let (expression, base) = split_output_base(query.trim());
+    let evaluation = evaluate(expression)?;
+
+    let formatted = match base {
+        Some(base) => format_in_base(&evaluation.value, base)?,
+        None => format_number(&evaluation.value),
+    };
+    // Don't turn a search for "pi" or "42" into a calculation
+    let unchanged = formatted == expression.replace(' ', "");
+    if base.is_none() && (unchanged || evaluation.only_identifiers) {
+        return Err("Not a math expression".to_string());
+    }
+
+    Ok(SearchResult {
+        id: "calculator".to_string(),
+        title: format!("{} = {}", query.trim(), formatted),
+        description: "Press Enter to copy result to clipboard".to_string(),
+        icon: Some("🧮".to_string()),
+        action_type: ActionType::CopyCalculation,
+        action_data: formatted,
+        score: 0.9,
+        matched_ranges: Vec::new(),
+        secondary_actions: Vec::new(),
+        action_label: None,
+    })
+}
+
+/// Makes a copied result available as `ans` in later calculations.
+pub fn remember_answer(value: &str) {
+    if let Ok(evaluation) = evaluate(value) {
+        if let Ok(mut answer) = LAST_ANSWER.lock() {
+            *answer = Some(evaluation.value);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Base {
+    Binary,
+    Octal,
+    Decimal,
+    Hexadecimal,
+}
+
+// "255 in hex", "0xff to dec", "10 as bin"
+fn split_output_base(query: &str) -> (&str, Option<Base>) {
+    for separator in [" in ", " to ", " as "] {
+        let Some(position) = rfind_ignore_ascii_case(query, separator) else {
+            continue;
+        };
+        let base = match query[position + separator.len()..]
+            .trim()
+            .to_lowercase()
+            .as_str()
+        {
+            "hex" | "hexadecimal" => Base::Hexadecimal,
+            "bin" | "binary" => Base::Binary,
+            "oct" | "octal" => Base::Octal,
+            "dec" | "decimal" => Base::Decimal,
+            _ => continue,
+        };
+        return (query[..position].trim(), Some(base));
+    }
+    (query, None)
+}
+
+/// Byte offset of the last occurrence of `needle`, ignoring ASCII case.
+/// Unlike searching a lowercased copy, the offset is always valid in `haystack`.
+pub(super) fn rfind_ignore_ascii_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack.char_indices().rev().find_map(|(i, _)| {
+        haystack
+            .get(i..i + needle.len())
+            .filter(|candidate| candidate.eq_ignore_ascii_case(needle))
+            .map(|_| i)
+    })
+}
+
+/// Formats a result for display: exact integers in full, other values
+/// rounded to a fixed number of decimals.
+pub(crate) fn format_number(value: &BigDecimal) -> String {
+    let rounded = value.round(DISPLAY_DECIMALS).normalized();
+
+    // Too small to show as a plain decimal
+    if rounded.is_zero() && !value.is_zero() {
+        return value.with_prec(16).normalized().to_scientific_notation();
+    }
+    let limit = BigDecimal::new(BigInt::one(), -MAX_PLAIN_DIGITS);
+    if rounded.abs() >= limit {
+        return rounded.with_prec(16).normalized().to_scientific_notation();
+    }
+    rounded.to_plain_string()
+}
+
+fn format_in_base(value: &BigDecimal, base: Base) -> Result<String, String> {
+    if !value.is_integer() {
+        return Err("Only integers can be shown in another base".to_string());
+    }
+    let (integer, _) = value.with_scale(0).into_bigint_and_exponent();
+    let (prefix, radix) = match base {
+        Base::Binary => ("0b", 2),
+        Base::Octal => ("0o", 8),
+        Base::Decimal => ("", 10),
+        Base::Hexadecimal => ("0x", 16),
+    };
+
+    let sign = if integer.is_negative() { "-" } else { "" };
+    Ok(format!(
+        "{}{}{}",
+        sign,
+        prefix,
+        integer.abs().to_str_radix(radix)
+    ))
+}
+
+pub(crate) struct Evaluation {
+    pub value: BigDecimal,
+    /// Whether the expression was only a constant or variable such as `pi`
+    pub only_identifiers: bool,
+}
+
+/// Evaluates an arithmetic expression exactly where possible, falling back
+/// to floating point for transcendental functions.
+pub(crate) fn evaluate(expression: &str) -> Result<Evaluation, String> {
+    let tokens = tokenize(expression)?;
+    if tokens.is_empty() {
+        return Err("Empty expression".to_string());
+    }
+
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        operations: 0,
+        identifiers: 0,
+    };
+    let value = parser.expression()?;
+    if parser.position != parser.tokens.len() {
+        return Err("Unexpected input after expression".to_string());
+    }
+
+    Ok(Evaluation {
+        value,
+        only_identifiers: parser.operations == 0 && parser.identifiers > 0,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(BigDecimal),
+    Identifier(String),
+    Operator(char),
+    OpenParen,
+    CloseParen,
+    Comma,
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = expression.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            ' ' | '\t' => i += 1,
+            '0'..='9' | '.' => {
+                let (number, next) = read_number(&chars, i)?;
+                tokens.push(Token::Number(number));
+                i = next;
+            }
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                tokens.push(Token::Operator('^'));
+                i += 2;
+            }
+            '+' | '-' | '*' | '/' | '%' | '^' | '!' => {
+                tokens.push(Token::Operator(c));
+                i += 1;
+            }
+            '×' | '·' => {
+                tokens.push(Token::Operator('*'));
+                i += 1;
+            }
+            '÷' => {
+                tokens.push(Token::Operator('/'));
+                i += 1;
+            }
+            '(' => {
+                tokens.push(Token::OpenParen);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::CloseParen);
+                i += 1;
+            }
+            ',' => {
+                tokens.push(Token::Comma);
+                i += 1;
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                let identifier: String = chars[start..i].iter().collect();
+                tokens.push(Token::Identifier(identifier.to_lowercase()));
+            }
+            _ => return Err(format!("Unexpected character '{}'", c)),
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Reads a decimal (`1.5`, `2e-3`) or prefixed integer (`0xff`, `0b101`, `0o17`) literal.
+fn read_number(chars: &[char], start: usize) -> Result<(BigDecimal, usize), String> {
+    let radix = match (chars[start], chars.get(start + 1)) {
+        ('0', Some('x' | 'X')) => Some(16),
+        ('0', Some('b' | 'B')) => Some(2),
+        ('0', Some('o' | 'O')) => Some(8),
+        _ => None,
+    };
+    if let Some(radix) = radix {
+        let mut end = start + 2;
+        while end < chars.len() && (chars[end].is_digit(radix) || chars[end] == '_') {
+            end += 1;
+        }
+        let digits: String = chars[start + 2..end]
+            .iter()
+            .filter(|&&c| c != '_')
+            .collect();
+        let integer = BigInt::parse_bytes(digits.as_bytes(), radix)
+            .ok_or_else(|| "Invalid number".to_string())?;
+        return Ok((bounded(BigDecimal::from(integer))?, end));
+    }
+
+    let mut end = start;
+    while end < chars.len() && (chars[end].is_ascii_digit() || chars[end] == '.') {
+        end += 1;
+    }
+    // Only an `e` followed by an exponent belongs to the number; `2e` is not one
+    if matches!(chars.get(end), Some('e' | 'E')) {
+        let mut exponent_end = end + 1;
+        if matches!(chars.get(exponent_end), Some('+' | '-')) {
+            exponent_end += 1;
+        }
+        if chars.get(exponent_end).is_some_and(|c| c.is_ascii_digit()) {
+            end = exponent_end;
+            while end < chars.len() && chars[end].is_ascii_digit() {
+                end += 1;
+            }
+        }
+    }
+
+    let literal: String = chars[start..end].iter().collect();
+    let number =
+        BigDecimal::from_str(&literal).map_err(|_| format!("Invalid number '{}'", literal))?;
+    Ok((bounded(number)?, end))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    operations: usize,
+    identifiers: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn eat_operator(&mut self, operators: &[char]) -> Option<char> {
+        match self.peek() {
+            Some(Token::Operator(op)) if operators.contains(op) => {
+                let op = *op;
+                self.position += 1;
+                self.operations += 1;
+                Some(op)
+            }
+            _ => None,
+        }
+    }
+
+    // expression := term (('+' | '-') term)*
+    fn expression(&mut self) -> Result<BigDecimal, String> {
+        let mut value = self.term()?;
+        while let Some(op) = self.eat_operator(&['+', '-']) {
+            let rhs = self.term()?;
+            value = bounded(if op == '+' { value + rhs } else { value - rhs })?;
+        }
+        Ok(value)
+    }
+
+    // term := unary (('*' | '/' | '%') unary)*
+    fn term(&mut self) -> Result<BigDecimal, String> {
+        let mut value = self.unary()?;
+        while let Some(op) = self.eat_operator(&['*', '/', '%']) {
+            let rhs = self.unary()?;
+            value = bounded(match op {
+                '*' => value * rhs,
+                _ if rhs.is_zero() => return Err("Division by zero".to_string()),
+                '/' => value / rhs,
+                _ => value % rhs,
+            })?;
+        }
+        Ok(value)
+    }
+
+    // unary := ('-' | '+') unary | power
+    fn unary(&mut self) -> Result<BigDecimal, String> {
+        match self.eat_operator(&['-', '+']) {
+            Some('-') => Ok(-self.unary()?),
+            Some(_) => self.unary(),
+            None => self.power(),
+        }
+    }
+
+    // power := postfix ('^' unary)?, right-associative so that 2^3^2 = 2^9
+    fn power(&mut self) -> Result<BigDecimal, String> {
+        let base = self.postfix()?;
+        if self.eat_operator(&['^']).is_none() {
+            return Ok(base);
+        }
+        let exponent = self.unary()?;
+        power(&base, &exponent)
+    }
+
+    // postfix := primary '!'*
+    fn postfix(&mut self) -> Result<BigDecimal, String> {
+        let mut value = self.primary()?;
+        while self.eat_operator(&['!']).is_some() {
+            value = factorial(&value)?;
+        }
+        Ok(value)
+    }
+
+    fn primary(&mut self) -> Result<BigDecimal, String> {
+        match self.next() {
+            Some(Token::Number(number)) => Ok(number),
+            Some(Token::OpenParen) => {
+                let value = self.expression()?;
+                match self.next() {
+                    Some(Token::CloseParen) => Ok(value),
+                    _ => Err("Missing closing parenthesis".to_string()),
+                }
+            }
+            Some(Token::Identifier(name)) => {
+                if self.peek() == Some(&Token::OpenParen) {
+                    self.position += 1;
+                    self.operations += 1;
+                    let arguments = self.arguments()?;
+                    call_function(&name, &arguments)
+                } else {
+                    self.identifiers += 1;
+                    constant(&name)
+                }
+            }
+            _ => Err("Expected a number".to_string()),
+        }
+    }
+
+    fn arguments(&mut self) -> Result<Vec<BigDecimal>, String> {
+        let mut arguments = Vec::new();
+        if self.peek() == Some(&Token::CloseParen) {
+            self.position += 1;
+            return Ok(arguments);
+        }
+        loop {
+            arguments.push(self.expression()?);
+            match self.next() {
+                Some(Token::Comma) => continue,
+                Some(Token::CloseParen) => return Ok(arguments),
+                _ => return Err("Missing closing parenthesis".to_string()),
+            }
+        }
+    }
+}
+
+fn constant(name: &str) -> Result<BigDecimal, String> {
+    let digits = match name {
+        "pi" | "π" => "3.14159265358979323846264338327950288419716939937510",
+        "tau" | "τ" => "6.28318530717958647692528676655900576839433879875021",
+        "e" => "2.71828182845904523536028747135266249775724709369995",
+        "phi" | "φ" => "1.61803398874989484820458683436563811772030917980576",
+        "ans" => {
+            return LAST_ANSWER
+                .lock()
+                .ok()
+                .and_then(|answer| answer.clone())
+                .ok_or_else(|| "No previous result".to_string());
+        }
+        _ => return Err(format!("Unknown constant '{}'", name)),
+    };
+    BigDecimal::from_str(digits).map_err(|e| e.to_string())
+}
+
+fn call_function(name: &str, arguments: &[BigDecimal]) -> Result<BigDecimal, String> {
+    let single = || match arguments {
+        [argument] => Ok(argument),
+        _ => Err(format!("{}() takes one argument", name)),
+    };
+
+    match name {
+        "abs" => Ok(single()?.abs()),
+        "floor" => Ok(single()?.with_scale_round(0, bigdecimal::RoundingMode::Floor)),
+        "ceil" => Ok(single()?.with_scale_round(0, bigdecimal::RoundingMode::Ceiling)),
+        "round" => match arguments {
+            [value] => Ok(value.with_scale_round(0, bigdecimal::RoundingMode::HalfUp)),
+            [value, digits] => {
+                let digits = digits
+                    .to_i64()
+                    .ok_or("Invalid number of digits")?
+                    .clamp(-(MAX_SCALE as i64), MAX_SCALE as i64);
+                Ok(value.with_scale_round(digits, bigdecimal::RoundingMode::HalfUp))
+            }
+            _ => Err("round() takes one or two arguments".to_string()),
+        },
+        "trunc" => Ok(single()?.with_scale_round(0, bigdecimal::RoundingMode::Down)),
+        "sqrt" => single()?
+            .sqrt()
+            .ok_or_else(|| "Square root of a negative number".to_string()),
+        "cbrt" => Ok(single()?.cbrt()),
+        "exp" => {
+            let value = single()?;
+            // Beyond this the result has more than MAX_SCALE digits
+            if value.abs() > MAX_EXP_ARGUMENT {
+                return Err("Number out of range".to_string());
+            }
+            Ok(value.exp())
+        }
+        "min" | "max" if !arguments.is_empty() => {
+            let mut values = arguments.iter();
+            let first = values.next().cloned().unwrap_or_default();
+            Ok(values.fold(first, |best, value| {
+                let better = if name == "min" {
+                    value < &best
+                } else {
+                    value > &best
+                };
+                if better {
+                    value.clone()
+                } else {
+                    best
+                }
+            }))
+        }
+        "log" if arguments.len() == 2 => {
+            let value = to_f64(&arguments[0])?;
+            let base = to_f64(&arguments[1])?;
+            from_f64(value.ln() / base.ln())
+        }
+        "ln" | "log" | "log10" | "log2" | "sin" | "cos" | "tan" | "asin" | "acos" | "atan"
+        | "sinh" | "cosh" | "tanh" => {
+            let value = to_f64(single()?)?;
+            let result = match name {
+                "ln" => value.ln(),
+                "log" | "log10" => value.log10(),
+                "log2" => value.log2(),
+                "sin" => value.sin(),
+                "cos" => value.cos(),
+                "tan" => value.tan(),
+                "asin" => value.asin(),
+                "acos" => value.acos(),
+                "atan" => value.atan(),
+                "sinh" => value.sinh(),
+                "cosh" => value.cosh(),
+                _ => value.tanh(),
+            };
+            from_f64(result)
+        }
+        "fact" | "factorial" => factorial(single()?),
+        _ => Err(format!("Unknown function '{}'", name)),
+    }
+}
+
+fn power(base: &BigDecimal, exponent: &BigDecimal) -> Result<BigDecimal, String> {
+    if exponent.is_integer() {
+        let exponent = exponent
+            .to_i64()
+            .filter(|exponent| exponent.abs() <= MAX_EXPONENT)
+            .ok_or("Exponent too large")?;
+        if base.is_zero() && exponent < 0 {
+            return Err("Division by zero".to_string());
+        }
+        // Estimate the size of the result before spending time on it
+        let (digits, scale) = size(base);
+        let times = exponent.unsigned_abs();
+        if digits * times > MAX_DIGITS || scale * times > MAX_SCALE {
+            return Err("Result too large".to_string());
+        }
+        return bounded(base.powi(exponent));
+    }
+    from_f64(to_f64(base)?.powf(to_f64(exponent)?))
+}
+
+fn factorial(value: &BigDecimal) -> Result<BigDecimal, String> {
+    if !value.is_integer() || value.is_negative() {
+        return Err("Factorial needs a non-negative integer".to_string());
+    }
+    let n = value
+        .to_u64()
+        .filter(|n| *n <= MAX_FACTORIAL)
+        .ok_or("Factorial too large")?;
+    Ok((2..=n).fold(BigDecimal::one(), |product, i| {
+        product * BigDecimal::from(i)
+    }))
+}
+
+/// Rejects values too large to keep computing with exactly.
+pub(super) fn bounded(value: BigDecimal) -> Result<BigDecimal, String> {
+    let (digits, scale) = size(&value);
+    if digits > MAX_DIGITS || scale > MAX_SCALE {
+        return Err("Number out of range".to_string());
+    }
+    Ok(value)
+}
+
+// Approximate decimal digits of the unscaled integer, and the absolute scale
+fn size(value: &BigDecimal) -> (u64, u64) {
+    let (integer, scale) = value.as_bigint_and_scale();
+    let digits = integer.bits() * 30_103 / 100_000 + 1;
+    (digits, scale.unsigned_abs())
+}
+
+fn to_f64(value: &BigDecimal) -> Result<f64, String> {
+    value
+        .to_f64()
+        .ok_or_else(|| "Number out of range".to_string())
+}
+
+fn from_f64(value: f64) -> Result<BigDecimal, String> {
+    if !value.is_finite() {
+        return Err("Result is not a number".to_string());
+    }
+    // The shortest representation, not the exact binary expansion
+    BigDecimal::from_str(&value.to_string()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calc(expression: &str) -> String {
+        format_number(&evaluate(expression).unwrap().value)
+    }
+
+    #[test]
+    fn evaluates_decimals_exactly() {
+        assert_eq!(calc("0.1+0.2"), "0.3");
+        assert_eq!(calc("1/3"), "0.333333333333333");
+        assert_eq!(calc("2^3^2"), "512");
+        assert_eq!(calc("-2^2"), "-4");
+    }
+
+    #[test]
+    fn reads_prefixed_integers() {
+        assert_eq!(calc("0xff"), "255");
+        assert_eq!(calc("0b1010 + 0o17"), "25");
+        assert_eq!(calc("0xFF_FF"), "65535");
+    }
+
+    #[tokio::test]
+    async fn shows_results_in_other_bases() {
+        assert_eq!(calculate("255 in hex").await.unwrap().action_data, "0xff");
+        assert_eq!(calculate("-5 to bin").await.unwrap().action_data, "-0b101");
+        assert_eq!(calculate("0o17 as dec").await.unwrap().action_data, "15");
+        assert!(calculate("1.5 in hex").await.is_err());
+        assert!(calculate("İİİ in hex").await.is_err());
+    }
+
+    #[test]
+    fn computes_remainders_and_factorials() {
+        assert_eq!(calc("10 % 3"), "1");
+        assert_eq!(calc("5.5 % 2"), "1.5");
+        assert_eq!(calc("5!"), "120");
+        assert_eq!(calc("3!!"), "720");
+        assert_eq!(calc("0!"), "1");
+        assert!(evaluate("1 % 0").is_err());
+        assert!(evaluate("(-1)!").is_err());
+        assert!(evaluate("1.5!").is_err());
+    }
+
+    #[test]
+    fn rejects_numbers_too_large_to_compute() {
+        for expression in [
+            "(2^10000)^10000",
+            "1000!^1000",
+            "1e99999999+1",
+            "1e-99999999+1",
+            "2^100000",
+            "1001!",
+            "exp(1e10000)",
+        ] {
+            assert!(evaluate(expression).is_err(), "{expression}");
+        }
+        assert!(calc("2^10000").starts_with("1.99506311688075"));
+        assert_eq!(calc("1e10000 / 1e9999"), "10");
+    }
+
+    #[test]
+    fn clamps_rounding_digits() {
+        assert_eq!(calc("round(1.23456, 99999999999)"), "1.23456");
+        assert_eq!(calc("round(1.23456, 2)"), "1.23");
+        assert_eq!(calc("round(1234.5, -99999999999)"), "0");
+    }
 }
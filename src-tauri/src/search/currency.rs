@@ -0,0 +1,355 @@
+use crate::config::{load_config, write_atomically};
+use crate::models::{ActionType, SearchResult};
+use bigdecimal::{BigDecimal, Zero};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::calculator::format_number;
+use super::provider::{Cancellation, SearchProvider};
+use super::units::split_conversion;
+
+// Downloaded rates older than this are refreshed in the background
+const REFRESH_INTERVAL: u64 = 12 * 60 * 60;
+// Failed downloads are not retried sooner than this, so offline searches
+// don't hit the network on every keystroke
+const RETRY_INTERVAL: u64 = 5 * 60;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Rate table, loaded from the saved snapshot on first use
+static RATES: OnceLock<RwLock<Option<Arc<RateTable>>>> = OnceLock::new();
+static REFRESHING: AtomicBool = AtomicBool::new(false);
+static LAST_ATTEMPT: AtomicU64 = AtomicU64::new(0);
+
+// Symbols and names accepted in place of ISO 4217 codes
+const ALIASES: &[(&str, &str)] = &[
+    ("$", "USD"),
+    ("dollar", "USD"),
+    ("dollars", "USD"),
+    ("€", "EUR"),
+    ("euro", "EUR"),
+    ("euros", "EUR"),
+    ("£", "GBP"),
+    ("pound", "GBP"),
+    ("pounds", "GBP"),
+    ("¥", "JPY"),
+    ("yen", "JPY"),
+    ("₹", "INR"),
+    ("rupee", "INR"),
+    ("rupees", "INR"),
+    ("₩", "KRW"),
+    ("won", "KRW"),
+    ("₽", "RUB"),
+    ("₺", "TRY"),
+    ("₪", "ILS"),
+    ("฿", "THB"),
+    ("zł", "PLN"),
+    ("franc", "CHF"),
+    ("francs", "CHF"),
+    ("yuan", "CNY"),
+    ("renminbi", "CNY"),
+];
+
+// Codes in circulation according to ISO 4217, sorted for binary search, so
+// that "100 mph to kph" is not taken for a currency conversion
+const ISO_CODES: &[&str] = &[
+    "AED", "AFN", "ALL", "AMD", "ANG", "AOA", "ARS", "AUD", "AWG", "AZN", "BAM", "BBD", "BDT",
+    "BGN", "BHD", "BIF", "BMD", "BND", "BOB", "BRL", "BSD", "BTN", "BWP", "BYN", "BZD", "CAD",
+    "CDF", "CHF", "CLP", "CNY", "COP", "CRC", "CUP", "CVE", "CZK", "DJF", "DKK", "DOP", "DZD",
+    "EGP", "ERN", "ETB", "EUR", "FJD", "FKP", "GBP", "GEL", "GHS", "GIP", "GMD", "GNF", "GTQ",
+    "GYD", "HKD", "HNL", "HTG", "HUF", "IDR", "ILS", "INR", "IQD", "IRR", "ISK", "JMD", "JOD",
+    "JPY", "KES", "KGS", "KHR", "KMF", "KPW", "KRW", "KWD", "KYD", "KZT", "LAK", "LBP", "LKR",
+    "LRD", "LSL", "LYD", "MAD", "MDL", "MGA", "MKD", "MMK", "MNT", "MOP", "MRU", "MUR", "MVR",
+    "MWK", "MXN", "MYR", "MZN", "NAD", "NGN", "NIO", "NOK", "NPR", "NZD", "OMR", "PAB", "PEN",
+    "PGK", "PHP", "PKR", "PLN", "PYG", "QAR", "RON", "RSD", "RUB", "RWF", "SAR", "SBD", "SCR",
+    "SDG", "SEK", "SGD", "SHP", "SLE", "SOS", "SRD", "SSP", "STN", "SVC", "SYP", "SZL", "THB",
+    "TJS", "TMT", "TND", "TOP", "TRY", "TTD", "TWD", "TZS", "UAH", "UGX", "USD", "UYU", "UZS",
+    "VES", "VND", "VUV", "WST", "XAF", "XCD", "XCG", "XOF", "XPF", "YER", "ZAR", "ZMW", "ZWG",
+];
+
+/// Exchange rates as served by the configured endpoint, e.g.
+/// `{"base": "EUR", "date": "2026-10-16", "rates": {"USD": 1.0832, ...}}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateTable {
+    pub base: String,
+    /// Day the rates were published
+    pub date: String,
+    /// Units of each currency per unit of `base`
+    pub rates: HashMap<String, f64>,
+    /// When the table was downloaded, in seconds since the Unix epoch
+    #[serde(default)]
+    pub fetched_at: u64,
+    /// Endpoint the table was downloaded from
+    #[serde(default)]
+    pub source: String,
+}
+
+impl RateTable {
+    // Units of `currency` per unit of the base currency
+    fn rate(&self, currency: &str) -> Option<BigDecimal> {
+        if currency == self.base {
+            return Some(BigDecimal::from(1));
+        }
+        let rate = self.rates.get(currency)?;
+        BigDecimal::from_str(&rate.to_string()).ok()
+    }
+}
+
+pub struct CurrencyProvider;
+
+#[async_trait::async_trait]
+impl SearchProvider for CurrencyProvider {
+    fn name(&self) -> &'static str {
+        "currency"
+    }
+
+    fn title(&self) -> &'static str {
+        "Currency conversion"
+    }
+
+    fn priority(&self) -> i32 {
+        35
+    }
+
+    async fn query(
+        &self,
+        query: &str,
+        _cancellation: &Cancellation,
+    ) -> Result<Vec<SearchResult>, String> {
+        let query = symbol_after_amount(query);
+        let (amount, from, to) = split_conversion(&query).ok_or("Not a currency conversion")?;
+        let from = currency_code(from).ok_or("Not a currency")?;
+        let to = currency_code(to).ok_or("Not a currency")?;
+
+        let config = load_config().await.unwrap_or_default();
+        let rates = get_rates(&config.exchange_rates_url)
+            .ok_or("Exchange rates have not been downloaded yet")?;
+
+        convert_currency(&amount, &from, &to, &rates).map(|result| vec![result])
+    }
+}
+
+/// Converts `amount` between two currencies with the given rates.
+pub fn convert_currency(
+    amount: &BigDecimal,
+    from: &str,
+    to: &str,
+    rates: &RateTable,
+) -> Result<SearchResult, String> {
+    let from_rate = rates
+        .rate(from)
+        .ok_or_else(|| format!("Unknown currency {}", from))?;
+    let to_rate = rates
+        .rate(to)
+        .ok_or_else(|| format!("Unknown currency {}", to))?;
+    if from_rate.is_zero() {
+        return Err(format!("No exchange rate for {}", from));
+    }
+
+    let rate = &to_rate / &from_rate;
+    let converted = round_amount(&(amount * &rate));
+    let formatted = format_number(&converted);
+
+    Ok(SearchResult {
+        id: "currency_conversion".to_string(),
+        title: format!("{} {} = {} {}", format_number(amount), from, formatted, to),
+        description: format!(
+            "1 {} = {} {} · Rates from {} · Press Enter to copy result to clipboard",
+            from,
+            format_number(&rate.with_prec(6)),
+            to,
+            rates.date
+        ),
+        icon: Some("💱".to_string()),
+        action_type: ActionType::CopyToClipboard,
+        action_data: formatted,
+        score: 0.9,
+        matched_ranges: Vec::new(),
+        secondary_actions: Vec::new(),
+        action_label: None,
+    })
+}
+
+// Cents for ordinary amounts, a few significant digits for fractions
+fn round_amount(amount: &BigDecimal) -> BigDecimal {
+    if amount.abs() < 1 {
+        amount.with_prec(4)
+    } else {
+        amount.round(2)
+    }
+}
+
+// "$100 in €" is read as "100 $ in €"
+fn symbol_after_amount(query: &str) -> String {
+    let query = query.trim();
+    let symbol = ALIASES
+        .iter()
+        .map(|(alias, _)| *alias)
+        .filter(|alias| !alias.chars().all(|c| c.is_alphabetic()))
+        .find(|symbol| query.starts_with(symbol));
+    match symbol {
+        Some(symbol) => {
+            let rest = &query[symbol.len()..];
+            let amount_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            format!("{} {}{}", &rest[..amount_end], symbol, &rest[amount_end..])
+        }
+        None => query.to_string(),
+    }
+}
+
+fn currency_code(name: &str) -> Option<String> {
+    let name = name.trim();
+    if let Some((_, code)) = ALIASES
+        .iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(name))
+    {
+        return Some(code.to_string());
+    }
+
+    let code = name.to_ascii_uppercase();
+    ISO_CODES
+        .binary_search(&code.as_str())
+        .is_ok()
+        .then_some(code)
+}
+
+/// Returns the cached rate table, starting a download in the background when
+/// it is missing or outdated. Searching never waits for the network.
+fn get_rates(url: &str) -> Option<Arc<RateTable>> {
+    let rates = RATES
+        .get_or_init(|| RwLock::new(load_rates().ok().map(Arc::new)))
+        .read()
+        .ok()?
+        .clone();
+
+    let outdated = rates.as_ref().is_none_or(|rates| {
+        rates.source != url || now().saturating_sub(rates.fetched_at) > REFRESH_INTERVAL
+    });
+    if outdated {
+        start_refresh(url.to_string());
+    }
+
+    rates
+}
+
+fn start_refresh(url: String) {
+    let now = now();
+    if now.saturating_sub(LAST_ATTEMPT.load(Ordering::SeqCst)) < RETRY_INTERVAL
+        || REFRESHING.swap(true, Ordering::SeqCst)
+    {
+        return;
+    }
+    LAST_ATTEMPT.store(now, Ordering::SeqCst);
+
+    tokio::spawn(async move {
+        // Offline, the last saved snapshot stays in use
+        if let Err(e) = refresh_rates(&url).await {
+            eprintln!("Failed to refresh exchange rates: {}", e);
+        }
+        REFRESHING.store(false, Ordering::SeqCst);
+    });
+}
+
+/// Downloads the rate table from `url` and saves it as the new snapshot.
+pub async fn refresh_rates(url: &str) -> Result<(), String> {
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| e.to_string())?;
+    let mut rates: RateTable = response.json().await.map_err(|e| e.to_string())?;
+    rates.base = rates.base.to_ascii_uppercase();
+    rates.fetched_at = now();
+    rates.source = url.to_string();
+
+    save_rates(&rates)?;
+    if let Some(cache) = RATES.get() {
+        let mut cache = cache.write().map_err(|e| e.to_string())?;
+        *cache = Some(Arc::new(rates));
+    }
+    Ok(())
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+fn get_rates_path() -> Result<PathBuf, String> {
+    Ok(dirs::cache_dir()
+        .ok_or("Could not find cache directory")?
+        .join("lumina")
+        .join("exchange_rates.json"))
+}
+
+fn load_rates() -> Result<RateTable, String> {
+    let rates_content = std::fs::read_to_string(get_rates_path()?).map_err(|e| e.to_string())?;
+    serde_json::from_str(&rates_content).map_err(|e| e.to_string())
+}
+
+fn save_rates(rates: &RateTable) -> Result<(), String> {
+    let rates_json = serde_json::to_string(rates).map_err(|e| e.to_string())?;
+    write_atomically(&get_rates_path()?, rates_json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rates() -> RateTable {
+        RateTable {
+            base: "EUR".to_string(),
+            date: "2026-10-16".to_string(),
+            rates: HashMap::from([("USD".to_string(), 1.25), ("JPY".to_string(), 160.0)]),
+            fetched_at: 0,
+            source: String::new(),
+        }
+    }
+
+    fn convert(amount: &str, from: &str, to: &str) -> Result<String, String> {
+        let amount = BigDecimal::from_str(amount).unwrap();
+        convert_currency(&amount, from, to, &rates()).map(|result| result.action_data)
+    }
+
+    #[test]
+    fn converts_through_the_base_currency() {
+        assert_eq!(convert("100", "EUR", "USD").unwrap(), "125");
+        assert_eq!(convert("100", "USD", "EUR").unwrap(), "80");
+        assert_eq!(convert("10", "USD", "JPY").unwrap(), "1280");
+        // Fractions keep a few significant digits instead of rounding to zero
+        assert_eq!(convert("1", "JPY", "EUR").unwrap(), "0.00625");
+        assert_eq!(
+            convert("1", "EUR", "GBP").unwrap_err(),
+            "Unknown currency GBP"
+        );
+    }
+
+    #[test]
+    fn reads_symbols_before_the_amount() {
+        assert_eq!(symbol_after_amount("$100 in €"), "100 $ in €");
+        assert_eq!(symbol_after_amount("  €5 to usd"), "5 € to usd");
+        assert_eq!(symbol_after_amount("£1.50 to usd"), "1.50 £ to usd");
+        assert_eq!(symbol_after_amount("100 usd to eur"), "100 usd to eur");
+    }
+
+    #[test]
+    fn accepts_only_known_currencies() {
+        assert_eq!(currency_code("usd").as_deref(), Some("USD"));
+        assert_eq!(currency_code(" Euros ").as_deref(), Some("EUR"));
+        assert_eq!(currency_code("¥").as_deref(), Some("JPY"));
+        assert_eq!(currency_code("mph"), None);
+        assert_eq!(currency_code("kph"), None);
+        assert_eq!(currency_code("usdt"), None);
+    }
+}
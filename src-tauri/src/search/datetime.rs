@@ -0,0 +1,495 @@
+use crate::models::{ActionType, SearchResult};
+use chrono::{DateTime, Local, Months, NaiveDate, NaiveDateTime, TimeDelta, TimeZone};
+use chrono_tz::Tz;
+use regex::Regex;
+use std::sync::OnceLock;
+
+use super::provider::{Cancellation, SearchProvider};
+
+// Timestamps this large are taken to be in milliseconds
+const MILLISECOND_TIMESTAMP: i64 = 100_000_000_000;
+
+// Places that aren't named in the time zone database, by lowercase name
+const PLACES: &[(&str, &str)] = &[
+    ("san francisco", "America/Los_Angeles"),
+    ("seattle", "America/Los_Angeles"),
+    ("california", "America/Los_Angeles"),
+    ("washington", "America/New_York"),
+    ("boston", "America/New_York"),
+    ("miami", "America/New_York"),
+    ("atlanta", "America/New_York"),
+    ("dallas", "America/Chicago"),
+    ("houston", "America/Chicago"),
+    ("texas", "America/Chicago"),
+    ("beijing", "Asia/Shanghai"),
+    ("china", "Asia/Shanghai"),
+    ("delhi", "Asia/Kolkata"),
+    ("new delhi", "Asia/Kolkata"),
+    ("mumbai", "Asia/Kolkata"),
+    ("bangalore", "Asia/Kolkata"),
+    ("india", "Asia/Kolkata"),
+    ("osaka", "Asia/Tokyo"),
+    ("kyoto", "Asia/Tokyo"),
+    ("munich", "Europe/Berlin"),
+    ("frankfurt", "Europe/Berlin"),
+    ("hamburg", "Europe/Berlin"),
+    ("germany", "Europe/Berlin"),
+    ("france", "Europe/Paris"),
+    ("barcelona", "Europe/Madrid"),
+    ("spain", "Europe/Madrid"),
+    ("milan", "Europe/Rome"),
+    ("italy", "Europe/Rome"),
+    ("england", "Europe/London"),
+];
+
+// Abbreviations that aren't names in the time zone database, shown in upper case
+const ABBREVIATIONS: &[(&str, &str)] = &[
+    ("uk", "Europe/London"),
+    ("pst", "America/Los_Angeles"),
+    ("pdt", "America/Los_Angeles"),
+    ("mdt", "America/Denver"),
+    ("cst", "America/Chicago"),
+    ("cdt", "America/Chicago"),
+    ("edt", "America/New_York"),
+    ("bst", "Europe/London"),
+    ("cest", "Europe/Paris"),
+    ("ist", "Asia/Kolkata"),
+    ("jst", "Asia/Tokyo"),
+    ("aest", "Australia/Sydney"),
+];
+
+/// Answers date and time questions such as "now + 3 days", "time in Tokyo",
+/// "days until 2027-01-01", "1700000000 as date" and "2026-10-18 to unix".
+pub struct DateTimeProvider;
+
+#[async_trait::async_trait]
+impl SearchProvider for DateTimeProvider {
+    fn name(&self) -> &'static str {
+        "datetime"
+    }
+
+    fn title(&self) -> &'static str {
+        "Date and time"
+    }
+
+    fn priority(&self) -> i32 {
+        35
+    }
+
+    async fn query(
+        &self,
+        query: &str,
+        _cancellation: &Cancellation,
+    ) -> Result<Vec<SearchResult>, String> {
+        evaluate_datetime(query, Local::now()).map(|result| vec![result])
+    }
+}
+
+// A point in local time; dates without a time print without one
+#[derive(Debug, Clone, Copy)]
+struct Moment {
+    time: NaiveDateTime,
+    date_only: bool,
+}
+
+struct Answer {
+    title: String,
+    detail: String,
+    copy: String,
+}
+
+/// Evaluates a date or time query relative to `now`.
+pub fn evaluate_datetime(query: &str, now: DateTime<Local>) -> Result<SearchResult, String> {
+    let query = query.trim().to_lowercase();
+    let answer = time_in_place(&query, now)
+        .or_else(|| time_between(&query, now))
+        .or_else(|| timestamp_to_date(&query))
+        .or_else(|| date_to_timestamp(&query, now))
+        .or_else(|| date_arithmetic(&query, now))
+        .ok_or("Not a date or time query")?;
+
+    Ok(SearchResult {
+        id: "datetime".to_string(),
+        title: answer.title,
+        description: format!(
+            "{} · Press Enter to copy result to clipboard",
+            answer.detail
+        ),
+        icon: Some("🕒".to_string()),
+        action_type: ActionType::CopyToClipboard,
+        action_data: answer.copy,
+        score: 0.9,
+        matched_ranges: Vec::new(),
+        secondary_actions: Vec::new(),
+        action_label: None,
+    })
+}
+
+fn regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).expect("invalid date/time pattern"))
+}
+
+// "time in tokyo", "tokyo time"
+fn time_in_place(query: &str, now: DateTime<Local>) -> Option<Answer> {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    let captures = regex(
+        &PATTERN,
+        r"^(?:(?:time|clock|now) in (?P<after>.+)|(?P<before>.+?) time)$",
+    )
+    .captures(query)?;
+    let place = captures
+        .name("after")
+        .or_else(|| captures.name("before"))?
+        .as_str()
+        .trim();
+    let zone = find_time_zone(place)?;
+
+    let time = now.with_timezone(&zone);
+    let place = if place.contains('/') {
+        zone.name().replace('_', " ")
+    } else if let Some(city) = zone
+        .name()
+        .rsplit('/')
+        .next()
+        .map(|city| city.replace('_', " "))
+        .filter(|city| city.eq_ignore_ascii_case(place))
+    {
+        // Spelled as in the time zone database, like "New York" or "UTC"
+        city
+    } else if ABBREVIATIONS.iter().any(|(name, _)| *name == place) {
+        place.to_uppercase()
+    } else {
+        title_case(place)
+    };
+    Some(Answer {
+        title: format!("{} in {}", time.format("%H:%M"), place),
+        detail: format!(
+            "{} · {} (UTC{})",
+            time.format("%A, %-d %B %Y"),
+            zone.name(),
+            time.format("%:z")
+        ),
+        copy: time.format("%H:%M").to_string(),
+    })
+}
+
+fn find_time_zone(place: &str) -> Option<Tz> {
+    if let Some((_, zone)) = PLACES
+        .iter()
+        .chain(ABBREVIATIONS)
+        .find(|(name, _)| *name == place)
+    {
+        return zone.parse().ok();
+    }
+
+    // Match full names ("europe/berlin") and cities ("new york")
+    let wanted = place.replace(' ', "_");
+    chrono_tz::TZ_VARIANTS.iter().copied().find(|zone| {
+        let name = zone.name();
+        let city = name.rsplit('/').next().unwrap_or(name);
+        name.eq_ignore_ascii_case(&wanted) || city.eq_ignore_ascii_case(&wanted)
+    })
+}
+
+fn title_case(text: &str) -> String {
+    text.split(' ')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// "days until 2027-01-01", "hours since 2026-10-18 08:00"
+fn time_between(query: &str, now: DateTime<Local>) -> Option<Answer> {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    let captures = regex(
+        &PATTERN,
+        r"^(?P<unit>days|weeks|hours|minutes|seconds) (?:until|till|to|since|from) (?P<moment>.+)$",
+    )
+    .captures(query)?;
+    let target = parse_expression(&captures["moment"], now)?;
+    let unit = &captures["unit"];
+
+    let amount = if unit == "days" || unit == "weeks" {
+        let days = (target.time.date() - now.date_naive()).num_days();
+        if unit == "weeks" {
+            days / 7
+        } else {
+            days
+        }
+    } else {
+        let elapsed = target.time - now.naive_local();
+        match unit {
+            "hours" => elapsed.num_hours(),
+            "minutes" => elapsed.num_minutes(),
+            _ => elapsed.num_seconds(),
+        }
+    };
+
+    let unit = if amount.abs() == 1 {
+        unit.trim_end_matches('s')
+    } else {
+        unit
+    };
+    let direction = if amount < 0 { "since" } else { "until" };
+    Some(Answer {
+        title: format!(
+            "{} {} {} {}",
+            amount.abs(),
+            unit,
+            direction,
+            format_moment(&target)
+        ),
+        detail: target.time.format("%A, %-d %B %Y").to_string(),
+        copy: amount.abs().to_string(),
+    })
+}
+
+// "1700000000 as date"
+fn timestamp_to_date(query: &str) -> Option<Answer> {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    let captures = regex(
+        &PATTERN,
+        r"^(?P<timestamp>-?\d{1,16}) (?:as|to|in) (?P<zone>date|time|datetime|local|utc)$",
+    )
+    .captures(query)?;
+    let timestamp: i64 = captures["timestamp"].parse().ok()?;
+
+    let time = if timestamp.abs() >= MILLISECOND_TIMESTAMP {
+        DateTime::from_timestamp_millis(timestamp)?
+    } else {
+        DateTime::from_timestamp(timestamp, 0)?
+    };
+    let (title, zone) = if &captures["zone"] == "utc" {
+        (time.format("%Y-%m-%d %H:%M:%S").to_string(), "UTC")
+    } else {
+        let local = time.with_timezone(&Local);
+        (local.format("%Y-%m-%d %H:%M:%S").to_string(), "Local time")
+    };
+
+    Some(Answer {
+        copy: title.clone(),
+        title,
+        detail: format!("{} · {}", zone, time.to_rfc3339()),
+    })
+}
+
+// "2026-10-18 to unix", "now + 1 day as timestamp"
+fn date_to_timestamp(query: &str, now: DateTime<Local>) -> Option<Answer> {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    let captures = regex(
+        &PATTERN,
+        r"^(?P<moment>.+?) (?:as|to|in) (?:unix|unix time|unix timestamp|timestamp|epoch)$",
+    )
+    .captures(query)?;
+    let moment = parse_expression(&captures["moment"], now)?;
+    let local = Local.from_local_datetime(&moment.time).earliest()?;
+
+    let timestamp = local.timestamp().to_string();
+    Some(Answer {
+        title: timestamp.clone(),
+        detail: format!("Unix timestamp of {}", format_moment(&moment)),
+        copy: timestamp,
+    })
+}
+
+// "now + 3 days", "2026-10-18 - 2 weeks", "tomorrow"
+fn date_arithmetic(query: &str, now: DateTime<Local>) -> Option<Answer> {
+    let moment = parse_expression(query, now)?;
+    let formatted = format_moment(&moment);
+    Some(Answer {
+        copy: formatted.clone(),
+        title: formatted,
+        detail: moment.time.format("%A, %-d %B %Y").to_string(),
+    })
+}
+
+// A moment followed by any number of "+/- <amount> <unit>" offsets
+fn parse_expression(expression: &str, now: DateTime<Local>) -> Option<Moment> {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    static OFFSET: OnceLock<Regex> = OnceLock::new();
+    let captures = regex(
+        &PATTERN,
+        r"^(?P<base>.+?)(?P<offsets>(?:\s*[+-]\s*\d+\s*[a-z]+)*)$",
+    )
+    .captures(expression.trim())?;
+
+    let mut moment = parse_moment(captures["base"].trim(), now)?;
+    let offset_pattern = regex(&OFFSET, r"([+-])\s*(\d+)\s*([a-z]+)");
+    for offset in offset_pattern.captures_iter(&captures["offsets"]) {
+        let amount: i64 = offset[2].parse().ok()?;
+        let amount = if &offset[1] == "-" { -amount } else { amount };
+        moment = add_offset(moment, amount, &offset[3])?;
+    }
+    Some(moment)
+}
+
+fn parse_moment(text: &str, now: DateTime<Local>) -> Option<Moment> {
+    let today = now.date_naive();
+    let date = |date: NaiveDate| {
+        Some(Moment {
+            time: date.and_hms_opt(0, 0, 0)?,
+            date_only: true,
+        })
+    };
+
+    match text {
+        "now" => {
+            return Some(Moment {
+                time: now.naive_local(),
+                date_only: false,
+            })
+        }
+        "today" => return date(today),
+        "tomorrow" => return date(today.succ_opt()?),
+        "yesterday" => return date(today.pred_opt()?),
+        _ => {}
+    }
+
+    for format in [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%dt%H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dt%H:%M",
+    ] {
+        if let Ok(time) = NaiveDateTime::parse_from_str(text, format) {
+            return Some(Moment {
+                time,
+                date_only: false,
+            });
+        }
+    }
+    date(NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()?)
+}
+
+fn add_offset(moment: Moment, amount: i64, unit: &str) -> Option<Moment> {
+    let months = |months: i64| {
+        let shift = Months::new(u32::try_from(months.unsigned_abs()).ok()?);
+        if months < 0 {
+            moment.time.checked_sub_months(shift)
+        } else {
+            moment.time.checked_add_months(shift)
+        }
+    };
+
+    // Out-of-range results are not a date rather than a panic
+    let shift = |delta: Option<TimeDelta>| moment.time.checked_add_signed(delta?);
+
+    let (time, date_only) = match unit {
+        "s" | "sec" | "secs" | "second" | "seconds" => {
+            (shift(TimeDelta::try_seconds(amount))?, false)
+        }
+        "m" | "min" | "mins" | "minute" | "minutes" => {
+            (shift(TimeDelta::try_minutes(amount))?, false)
+        }
+        "h" | "hr" | "hrs" | "hour" | "hours" => (shift(TimeDelta::try_hours(amount))?, false),
+        "d" | "day" | "days" => (shift(TimeDelta::try_days(amount))?, moment.date_only),
+        "w" | "wk" | "wks" | "week" | "weeks" => {
+            (shift(TimeDelta::try_weeks(amount))?, moment.date_only)
+        }
+        "mo" | "month" | "months" => (months(amount)?, moment.date_only),
+        "y" | "yr" | "yrs" | "year" | "years" => {
+            (months(amount.checked_mul(12)?)?, moment.date_only)
+        }
+        _ => return None,
+    };
+    Some(Moment { time, date_only })
+}
+
+fn format_moment(moment: &Moment) -> String {
+    if moment.date_only {
+        moment.time.format("%Y-%m-%d").to_string()
+    } else {
+        moment.time.format("%Y-%m-%d %H:%M:%S").to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn now() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap()
+    }
+
+    fn evaluate(query: &str) -> SearchResult {
+        evaluate_datetime(query, now()).unwrap()
+    }
+
+    #[test]
+    fn adds_offsets_to_now() {
+        assert_eq!(evaluate("now + 3 days").action_data, "2026-10-21 12:00:00");
+        assert_eq!(evaluate("2026-10-18 - 2 weeks").action_data, "2026-10-04");
+        assert_eq!(evaluate("today + 1 month").action_data, "2026-11-18");
+    }
+
+    #[test]
+    fn rejects_offsets_out_of_range() {
+        assert!(evaluate_datetime("now + 999999999 days", now()).is_err());
+        assert!(evaluate_datetime("now - 999999999 weeks", now()).is_err());
+        assert!(evaluate_datetime("now + 9999999999999 seconds", now()).is_err());
+    }
+
+    #[test]
+    fn shows_time_in_places() {
+        let result = evaluate("time in Tokyo");
+        assert!(result.title.ends_with(" in Tokyo"), "{}", result.title);
+        assert!(result.description.contains("Asia/Tokyo"));
+
+        assert!(evaluate("time in rome").title.ends_with(" in Rome"));
+        assert!(evaluate("oslo time").title.ends_with(" in Oslo"));
+        assert!(evaluate("time in pst").title.ends_with(" in PST"));
+        assert!(evaluate("time in utc").title.ends_with(" in UTC"));
+        assert!(evaluate("time in new york").title.ends_with(" in New York"));
+    }
+
+    #[test]
+    fn shows_the_given_moment_in_places() {
+        let now = Utc
+            .with_ymd_and_hms(2026, 10, 18, 3, 30, 0)
+            .unwrap()
+            .with_timezone(&Local);
+        let result = evaluate_datetime("time in Tokyo", now).unwrap();
+        assert_eq!(result.title, "12:30 in Tokyo");
+        assert!(result
+            .description
+            .starts_with("Sunday, 18 October 2026 · Asia/Tokyo (UTC+09:00)"));
+
+        let result = evaluate_datetime("new york time", now).unwrap();
+        assert_eq!(result.title, "23:30 in New York");
+        assert!(result.description.starts_with("Saturday, 17 October 2026"));
+    }
+
+    #[test]
+    fn counts_days_until_a_date() {
+        let result = evaluate("days until 2027-01-01");
+        assert_eq!(result.action_data, "75");
+        assert_eq!(result.title, "75 days until 2027-01-01");
+    }
+
+    #[test]
+    fn converts_timestamps() {
+        let expected = DateTime::from_timestamp(1_700_000_000, 0)
+            .unwrap()
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string();
+        assert_eq!(evaluate("1700000000 as date").action_data, expected);
+        assert_eq!(
+            evaluate("1700000000 as utc").action_data,
+            "2023-11-14 22:13:20"
+        );
+
+        let midnight = Local.with_ymd_and_hms(2026, 10, 18, 0, 0, 0).unwrap();
+        assert_eq!(
+            evaluate("2026-10-18 to unix").action_data,
+            midnight.timestamp().to_string()
+        );
+    }
+}
@@ -0,0 +1,302 @@
+use crate::models::{ActionType, SearchResult};
+use base64::Engine;
+use md5::Md5;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use super::provider::{Cancellation, SearchProvider};
+
+const BASE64_ENGINES: [base64::engine::GeneralPurpose; 4] = [
+    base64::engine::general_purpose::STANDARD,
+    base64::engine::general_purpose::STANDARD_NO_PAD,
+    base64::engine::general_purpose::URL_SAFE,
+    base64::engine::general_purpose::URL_SAFE_NO_PAD,
+];
+
+const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+/// Transformations for developers, each started by a command word such as
+/// "base64 hello", "sha256 hello", "uuid" or "bases 0xff".
+pub struct EncodingProvider;
+
+#[async_trait::async_trait]
+impl SearchProvider for EncodingProvider {
+    fn name(&self) -> &'static str {
+        "encoding"
+    }
+
+    fn title(&self) -> &'static str {
+        "Encoding tools"
+    }
+
+    fn priority(&self) -> i32 {
+        35
+    }
+
+    async fn query(
+        &self,
+        query: &str,
+        _cancellation: &Cancellation,
+    ) -> Result<Vec<SearchResult>, String> {
+        encode(query)
+    }
+}
+
+/// Runs the transformation named by the first word of the query on the rest.
+pub fn encode(query: &str) -> Result<Vec<SearchResult>, String> {
+    let query = query.trim();
+    if let Some(result) = convert_to_base(query) {
+        return result.map(|result| vec![result]);
+    }
+
+    let (command, text) = query.split_once(' ').unwrap_or((query, ""));
+    let text = text.trim();
+    let result = |name: &str, description: &str, output: String| {
+        Ok(vec![encoding_result(name, description, output)])
+    };
+
+    match command.to_lowercase().as_str() {
+        "uuid" | "guid" if text.is_empty() => {
+            result("uuid", "Random UUID", uuid::Uuid::new_v4().to_string())
+        }
+        _ if text.is_empty() => Err("Nothing to encode".to_string()),
+        "base64" | "b64" => result(
+            "base64",
+            "Base64",
+            base64::engine::general_purpose::STANDARD.encode(text),
+        ),
+        "base64d" | "b64d" | "unbase64" => {
+            let bytes = BASE64_ENGINES
+                .iter()
+                .find_map(|engine| engine.decode(text).ok())
+                .ok_or("Invalid base64")?;
+            match String::from_utf8(bytes) {
+                Ok(decoded) => result("base64_decoded", "Base64 decoded", decoded),
+                Err(e) => result(
+                    "base64_decoded",
+                    "Base64 decoded (binary, shown as hex)",
+                    hex_dump(e.as_bytes()),
+                ),
+            }
+        }
+        "urlencode" | "urlenc" => result(
+            "url_encoded",
+            "URL encoded",
+            utf8_percent_encode(text, NON_ALPHANUMERIC).to_string(),
+        ),
+        "urldecode" | "urldec" => {
+            let decoded = percent_decode_str(&text.replace('+', " "))
+                .decode_utf8()
+                .map_err(|e| e.to_string())?
+                .into_owned();
+            result("url_decoded", "URL decoded", decoded)
+        }
+        "hex" => result("hexdump", "Hex dump", hex_dump(text.as_bytes())),
+        "unhex" => {
+            let decoded = decode_hex(text).ok_or("Invalid hex")?;
+            let decoded = String::from_utf8(decoded).map_err(|e| e.to_string())?;
+            result("unhex", "Hex decoded", decoded)
+        }
+        "md5" => result("md5", "MD5", digest::<Md5>(text)),
+        "sha1" => result("sha1", "SHA-1", digest::<Sha1>(text)),
+        "sha256" => result("sha256", "SHA-256", digest::<Sha256>(text)),
+        "bases" => {
+            let (negative, value) = parse_integer(text).ok_or("Not an integer")?;
+            let sign = if negative { "-" } else { "" };
+            Ok([
+                ("base_hex", "Hexadecimal", "0x", 16),
+                ("base_dec", "Decimal", "", 10),
+                ("base_oct", "Octal", "0o", 8),
+                ("base_bin", "Binary", "0b", 2),
+            ]
+            .into_iter()
+            .map(|(name, description, prefix, radix)| {
+                let output = format!("{}{}{}", sign, prefix, to_radix(value, radix));
+                encoding_result(name, description, output)
+            })
+            .collect())
+        }
+        _ => Err("Not an encoding command".to_string()),
+    }
+}
+
+fn encoding_result(name: &str, description: &str, output: String) -> SearchResult {
+    SearchResult {
+        id: format!("encoding_{}", name),
+        title: output.clone(),
+        description: format!("{} · Press Enter to copy result to clipboard", description),
+        icon: Some("🔣".to_string()),
+        action_type: ActionType::CopyToClipboard,
+        action_data: output,
+        score: 0.9,
+        matched_ranges: Vec::new(),
+        secondary_actions: Vec::new(),
+        action_label: None,
+    }
+}
+
+// "255 to base 36", "0xff in base 2"
+fn convert_to_base(query: &str) -> Option<Result<SearchResult, String>> {
+    let lower = query.to_lowercase();
+    let (number, radix) = lower
+        .split_once(" to base ")
+        .or_else(|| lower.split_once(" in base "))?;
+    let (negative, value) = parse_integer(number.trim())?;
+    let radix: u32 = radix.trim().parse().ok()?;
+    if !(2..=36).contains(&radix) {
+        return Some(Err("Bases range from 2 to 36".to_string()));
+    }
+
+    let sign = if negative { "-" } else { "" };
+    let output = format!("{}{}", sign, to_radix(value, radix));
+    Some(Ok(encoding_result(
+        "base",
+        &format!("Base {}", radix),
+        output,
+    )))
+}
+
+// Decimal, or hex/octal/binary with a 0x/0o/0b prefix
+fn parse_integer(text: &str) -> Option<(bool, u128)> {
+    let text = text.replace('_', "");
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.as_str()),
+    };
+    let lower = digits.to_lowercase();
+    let (radix, digits) = [("0x", 16), ("0o", 8), ("0b", 2)]
+        .iter()
+        .find_map(|(prefix, radix)| Some((*radix, lower.strip_prefix(prefix)?)))
+        .unwrap_or((10, lower.as_str()));
+    let value = u128::from_str_radix(digits, radix).ok()?;
+    Some((negative, value))
+}
+
+fn to_radix(mut value: u128, radix: u32) -> String {
+    if value == 0 {
+        return "0".to_string();
+    }
+    let mut digits = Vec::new();
+    while value > 0 {
+        digits.push(DIGITS[(value % radix as u128) as usize]);
+        value /= radix as u128;
+    }
+    digits.reverse();
+    String::from_utf8(digits).unwrap_or_default()
+}
+
+fn hex_dump(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    let digits: String = text
+        .split_whitespace()
+        .map(|chunk| chunk.trim_start_matches("0x"))
+        .collect();
+    if digits.len() % 2 != 0 {
+        return None;
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(digits.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn digest<D: Digest>(text: &str) -> String {
+    D::digest(text.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(query: &str) -> String {
+        let results = encode(query).unwrap();
+        assert_eq!(results.len(), 1);
+        results[0].action_data.clone()
+    }
+
+    #[test]
+    fn parses_prefixed_integers() {
+        assert_eq!(parse_integer("255"), Some((false, 255)));
+        assert_eq!(parse_integer("-0xFF"), Some((true, 255)));
+        assert_eq!(parse_integer("0o17"), Some((false, 15)));
+        assert_eq!(parse_integer("0b1010_1010"), Some((false, 170)));
+        assert_eq!(parse_integer("1_000_000"), Some((false, 1_000_000)));
+        assert_eq!(parse_integer("0x"), None);
+        assert_eq!(parse_integer("0b102"), None);
+        assert_eq!(parse_integer("12a"), None);
+    }
+
+    #[test]
+    fn formats_in_any_radix() {
+        assert_eq!(to_radix(0, 2), "0");
+        assert_eq!(to_radix(255, 16), "ff");
+        assert_eq!(to_radix(255, 2), "11111111");
+        assert_eq!(to_radix(35, 36), "z");
+        assert_eq!(to_radix(u128::MAX, 16), "f".repeat(32));
+        assert_eq!(output("-255 to base 36"), "-73");
+        assert!(encode("10 in base 37").is_err());
+    }
+
+    #[test]
+    fn decodes_hex() {
+        assert_eq!(decode_hex("68 69"), Some(b"hi".to_vec()));
+        assert_eq!(decode_hex("0x68 0x69"), Some(b"hi".to_vec()));
+        assert_eq!(decode_hex("6869"), Some(b"hi".to_vec()));
+        assert_eq!(decode_hex("686"), None);
+        assert_eq!(decode_hex("zz"), None);
+        assert_eq!(output("unhex 68 69"), "hi");
+    }
+
+    #[test]
+    fn decodes_every_base64_variant() {
+        assert_eq!(output("base64 hi"), "aGk=");
+        assert_eq!(output("base64d aGk="), "hi");
+        assert_eq!(output("b64d aGk"), "hi");
+        // Bytes that aren't UTF-8 are shown as hex
+        assert_eq!(output("base64d +/8="), "fb ff");
+        assert_eq!(output("base64d +/8"), "fb ff");
+        assert_eq!(output("base64d -_8="), "fb ff");
+        assert_eq!(output("base64d -_8"), "fb ff");
+        assert!(encode("base64d a").is_err());
+    }
+
+    #[test]
+    fn gives_every_output_its_own_id() {
+        assert_eq!(encode("hex hi").unwrap()[0].id, "encoding_hexdump");
+        let ids: Vec<String> = encode("bases 255")
+            .unwrap()
+            .into_iter()
+            .map(|result| result.id)
+            .collect();
+        assert_eq!(
+            ids,
+            [
+                "encoding_base_hex",
+                "encoding_base_dec",
+                "encoding_base_oct",
+                "encoding_base_bin"
+            ]
+        );
+    }
+
+    #[test]
+    fn lists_an_integer_in_every_base() {
+        let outputs: Vec<String> = encode("bases 0xff")
+            .unwrap()
+            .into_iter()
+            .map(|result| result.action_data)
+            .collect();
+        assert_eq!(outputs, ["0xff", "255", "0o377", "0b11111111"]);
+    }
+}
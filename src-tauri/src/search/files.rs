@@ -1,91 +1,170 @@
-use crate::models::{ActionType, SearchResult};
-use std::path::PathBuf;
-use walkdir::WalkDir;
+use super::applications::get_applications;
+use super::fuzzy::fuzzy_match;
+use super::index;
+use super::provider::{Cancellation, SearchProvider};
+use crate::desktop_entry::DesktopEntry;
+use crate::models::{ActionType, SearchResult, SecondaryAction};
+use crate::{icons, mime};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
 
-pub async fn search_files(query: &str) -> Result<Vec<SearchResult>, String> {
-    let mut results = Vec::new();
+// Only the best file matches are turned into results
+const MAX_FILE_RESULTS: usize = 50;
 
-    // Search in common directories
-    let search_dirs = get_search_directories();
+pub struct FilesProvider;
 
-    for base_dir in &search_dirs {
-        if !base_dir.exists() {
-            continue;
-        }
+#[async_trait::async_trait]
+impl SearchProvider for FilesProvider {
+    fn name(&self) -> &'static str {
+        "files"
+    }
 
-        let walker = WalkDir::new(base_dir)
-            .max_depth(get_max_depth(base_dir))
-            .into_iter()
-            .filter_map(|e| e.ok());
-
-        for entry in walker {
-            let path = entry.path();
-
-            if let Some(file_name) = path.file_name() {
-                let name = file_name.to_string_lossy().to_lowercase();
-                let query_lower = query.to_lowercase();
-
-                if name.contains(&query_lower) {
-                    let score = calculate_file_score(&name, &query_lower);
-
-                    if score > 0.1 {
-                        let result = SearchResult {
-                            id: format!("file_{}", results.len()),
-                            title: file_name.to_string_lossy().to_string(),
-                            description: path.to_string_lossy().to_string(),
-                            icon: get_file_icon(path),
-                            action_type: determine_action_type(path),
-                            action_data: path.to_string_lossy().to_string(),
-                            score,
-                        };
-                        results.push(result);
-                    }
-                }
-            }
-        }
+    fn title(&self) -> &'static str {
+        "Files"
+    }
+
+    fn priority(&self) -> i32 {
+        20
+    }
+
+    // Matching against a large index takes a while
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(2)
+    }
+
+    async fn query(
+        &self,
+        query: &str,
+        cancellation: &Cancellation,
+    ) -> Result<Vec<SearchResult>, String> {
+        search_files(query, cancellation).await
+    }
+}
+
+pub async fn search_files(
+    query: &str,
+    cancellation: &Cancellation,
+) -> Result<Vec<SearchResult>, String> {
+    // Query the background-maintained index instead of walking the disk
+    let index = index::snapshot();
+
+    let mut matches: Vec<_> = index
+        .entries()
+        // Stop matching once a newer query superseded this one
+        .take_while(|_| !cancellation.is_cancelled())
+        // Desktop entries are matched by the applications provider
+        .filter(|entry| !entry.name.ends_with(".desktop"))
+        .filter_map(|entry| fuzzy_match(query, &entry.name).map(|matched| (entry, matched)))
+        .filter(|(_, matched)| matched.score > 0.1)
+        .collect();
+
+    matches.sort_by(|(_, a), (_, b)| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    matches.truncate(MAX_FILE_RESULTS);
+    if cancellation.is_cancelled() {
+        return Err("Search cancelled".to_string());
     }
 
+    let applications = get_applications();
+    // Results often share a type, so resolve each type's handlers once
+    let mut handlers_by_type: HashMap<String, Vec<&DesktopEntry>> = HashMap::new();
+
+    let results = matches
+        .into_iter()
+        .map(|(entry, matched)| {
+            let path = Path::new(&entry.path);
+            let mime_type = mime::detect(path, entry.is_dir);
+            let handlers = handlers_by_type
+                .entry(mime_type.clone())
+                .or_insert_with(|| file_handlers(&mime_type, &applications));
+
+            let mut secondary_actions = open_with_actions(path, handlers);
+            secondary_actions.extend(file_actions(path));
+
+            SearchResult {
+                // Keyed by path so that launch history survives reindexing
+                id: format!("file_{}", entry.path),
+                title: entry.name.clone(),
+                description: entry.path.clone(),
+                icon: file_icon(path, &mime_type),
+                action_type: ActionType::OpenFile,
+                action_data: entry.path.clone(),
+                score: matched.score,
+                matched_ranges: matched.ranges,
+                secondary_actions,
+                action_label: handlers
+                    .first()
+                    .map(|handler| format!("Open with {}", handler.name)),
+            }
+        })
+        .collect();
+
     Ok(results)
 }
 
-fn get_search_directories() -> Vec<PathBuf> {
-    vec![
-        dirs::home_dir().unwrap_or_else(|| PathBuf::from("/")),
-        PathBuf::from("/usr/share/applications"),
-        PathBuf::from("/var/lib/flatpak/exports/share/applications"),
-        PathBuf::from("/home")
-            .join(std::env::var("USER").unwrap_or_default())
-            .join(".local/share/applications"),
-    ]
+/// Applications that can open the type, the default handler first.
+fn file_handlers<'a>(mime_type: &str, applications: &'a [DesktopEntry]) -> Vec<&'a DesktopEntry> {
+    let installed = applications
+        .iter()
+        .map(|app| (app.id.as_str(), app.mime_types.as_slice()));
+
+    mime::handlers(mime_type, installed)
+        .iter()
+        .filter_map(|id| applications.iter().find(|app| &app.id == id))
+        .collect()
 }
 
-fn get_max_depth(base_dir: &PathBuf) -> usize {
-    if base_dir.to_string_lossy().contains("applications") {
-        2
-    } else {
-        3
-    }
+// The default handler is the primary action, so only alternatives are listed
+fn open_with_actions(path: &Path, handlers: &[&DesktopEntry]) -> Vec<SecondaryAction> {
+    handlers
+        .iter()
+        .skip(1)
+        .map(|handler| SecondaryAction {
+            id: format!("open_with_{}", handler.id),
+            title: format!("Open with {}", handler.name),
+            action_type: ActionType::OpenWith,
+            action_data: format!(
+                "{}#{}",
+                handler.path.to_string_lossy(),
+                path.to_string_lossy()
+            ),
+        })
+        .collect()
 }
 
-fn calculate_file_score(name: &str, query: &str) -> f32 {
-    if name == query {
-        return 1.0;
-    }
-    if name.starts_with(query) {
-        return 0.8;
-    }
-    if name.contains(query) {
-        return 0.6;
+fn file_actions(path: &Path) -> Vec<SecondaryAction> {
+    let mut actions = Vec::new();
+
+    if let Some(parent) = path.parent() {
+        actions.push(SecondaryAction {
+            id: "open_folder".to_string(),
+            title: "Open containing folder".to_string(),
+            action_type: ActionType::OpenFile,
+            action_data: parent.to_string_lossy().to_string(),
+        });
     }
-    0.0
+    actions.push(SecondaryAction {
+        id: "copy_path".to_string(),
+        title: "Copy path".to_string(),
+        action_type: ActionType::CopyToClipboard,
+        action_data: path.to_string_lossy().to_string(),
+    });
+
+    actions
 }
 
-fn determine_action_type(path: &std::path::Path) -> ActionType {
-    if path.extension().map_or(false, |ext| ext == "desktop") {
-        ActionType::OpenApp
+/// Themed icon for the file's MIME type, falling back to an emoji.
+fn file_icon(path: &Path, mime_type: &str) -> Option<String> {
+    let themed = if mime_type == mime::DIRECTORY_TYPE {
+        icons::folder_icon()
     } else {
-        ActionType::OpenFile
-    }
+        icons::mime_icon(mime_type)
+    };
+    themed.or_else(|| get_file_icon(path))
 }
 
 pub fn get_file_icon(path: &std::path::Path) -> Option<String> {
@@ -0,0 +1,343 @@
+use crate::models::MatchRange;
+
+// Score bands, so that any exact-ish match outranks any fuzzy one
+const EXACT_SCORE: f32 = 1.0;
+const PREFIX_SCORE: f32 = 0.8;
+const WORD_SUBSTRING_SCORE: f32 = 0.7;
+const SUBSTRING_SCORE: f32 = 0.6;
+const SUBSEQUENCE_MIN_SCORE: f32 = 0.2;
+const SUBSEQUENCE_MAX_SCORE: f32 = 0.55;
+const TYPO_SCORE: f32 = 0.4;
+const TYPO_PENALTY: f32 = 0.1;
+
+// Subsequence scoring weights
+const MATCH_POINTS: i32 = 16;
+const BOUNDARY_BONUS: i32 = 10;
+const CAMEL_CASE_BONUS: i32 = 8;
+const CONSECUTIVE_BONUS: i32 = 6;
+const GAP_START_PENALTY: i32 = 3;
+const GAP_EXTEND_PENALTY: i32 = 1;
+
+#[derive(Debug, Clone)]
+pub struct FuzzyMatch {
+    pub score: f32,
+    /// Matched character ranges in the candidate
+    pub ranges: Vec<MatchRange>,
+}
+
+/// Matches `query` against `candidate` case-insensitively, trying exact,
+/// prefix and substring matches first, then subsequence matching with
+/// word-boundary and camelCase bonuses, then a small edit distance.
+pub fn fuzzy_match(query: &str, candidate: &str) -> Option<FuzzyMatch> {
+    let query: Vec<char> = query.trim().chars().map(to_lower).collect();
+    let original: Vec<char> = candidate.chars().collect();
+    let lower: Vec<char> = original.iter().copied().map(to_lower).collect();
+
+    if query.is_empty() || lower.is_empty() {
+        return None;
+    }
+
+    substring_match(&query, &original, &lower)
+        .or_else(|| subsequence_match(&query, &original, &lower))
+        .or_else(|| typo_match(&query, &original, &lower))
+}
+
+fn substring_match(query: &[char], original: &[char], lower: &[char]) -> Option<FuzzyMatch> {
+    if lower == query {
+        return Some(single_range(EXACT_SCORE, 0, query.len()));
+    }
+    if lower.starts_with(query) {
+        return Some(single_range(PREFIX_SCORE, 0, query.len()));
+    }
+
+    let mut fallback = None;
+    for start in 1..=lower.len().saturating_sub(query.len()) {
+        if &lower[start..start + query.len()] != query {
+            continue;
+        }
+        if boundary_bonus(original, start) > 0 {
+            return Some(single_range(WORD_SUBSTRING_SCORE, start, query.len()));
+        }
+        fallback.get_or_insert(start);
+    }
+
+    fallback.map(|start| single_range(SUBSTRING_SCORE, start, query.len()))
+}
+
+fn single_range(score: f32, start: usize, len: usize) -> FuzzyMatch {
+    FuzzyMatch {
+        score,
+        ranges: vec![MatchRange {
+            start,
+            end: start + len,
+        }],
+    }
+}
+
+/// Finds the best-scoring alignment of the query as a subsequence of the candidate.
+fn subsequence_match(query: &[char], original: &[char], lower: &[char]) -> Option<FuzzyMatch> {
+    let query: Vec<char> = query
+        .iter()
+        .copied()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    if query.is_empty() || !is_subsequence(&query, lower) {
+        return None;
+    }
+
+    let (n, m) = (query.len(), lower.len());
+    let bonuses: Vec<i32> = (0..m).map(|j| boundary_bonus(original, j)).collect();
+
+    // scores[i][j]: best score with query[i] matched at candidate[j]
+    // previous[i][j]: where query[i - 1] was matched on that best path
+    let mut scores = vec![vec![None::<i32>; m]; n];
+    let mut previous = vec![vec![0usize; m]; n];
+
+    for j in 0..m {
+        if lower[j] == query[0] {
+            // The first character counts its boundary bonus twice, favouring word starts
+            scores[0][j] = Some(MATCH_POINTS + bonuses[j] * 2);
+        }
+    }
+
+    for i in 1..n {
+        // Best (score, position) among earlier matches, with the gap penalty applied up to j
+        let mut gap_best: Option<(i32, usize)> = None;
+
+        for j in i..m {
+            if j >= 2 {
+                let extended = gap_best.map(|(score, k)| (score - GAP_EXTEND_PENALTY, k));
+                let opened = scores[i - 1][j - 2].map(|score| (score - GAP_START_PENALTY, j - 2));
+                gap_best = match (extended, opened) {
+                    (Some(a), Some(b)) => Some(if b.0 >= a.0 { b } else { a }),
+                    (a, b) => a.or(b),
+                };
+            }
+
+            if lower[j] != query[i] {
+                continue;
+            }
+
+            let consecutive = scores[i - 1][j - 1].map(|score| (score + CONSECUTIVE_BONUS, j - 1));
+            let best = match (consecutive, gap_best) {
+                (Some(a), Some(b)) => Some(if a.0 >= b.0 { a } else { b }),
+                (a, b) => a.or(b),
+            };
+
+            if let Some((score, k)) = best {
+                scores[i][j] = Some(score + MATCH_POINTS + bonuses[j]);
+                previous[i][j] = k;
+            }
+        }
+    }
+
+    let (mut position, raw_score) = scores[n - 1]
+        .iter()
+        .enumerate()
+        .filter_map(|(j, score)| score.map(|score| (j, score)))
+        .max_by_key(|&(_, score)| score)?;
+
+    let mut positions = vec![position; n];
+    for i in (1..n).rev() {
+        position = previous[i][position];
+        positions[i - 1] = position;
+    }
+
+    let ideal = (n as i32) * (MATCH_POINTS + BOUNDARY_BONUS + CONSECUTIVE_BONUS) + BOUNDARY_BONUS;
+    let quality = (raw_score as f32 / ideal as f32).clamp(0.0, 1.0);
+
+    Some(FuzzyMatch {
+        score: SUBSEQUENCE_MIN_SCORE + (SUBSEQUENCE_MAX_SCORE - SUBSEQUENCE_MIN_SCORE) * quality,
+        ranges: merge_positions(&positions),
+    })
+}
+
+/// Tolerates typos by comparing the query against the start of each word.
+/// Only tried once the cheaper matches failed.
+fn typo_match(query: &[char], original: &[char], lower: &[char]) -> Option<FuzzyMatch> {
+    let max_typos = match query.len() {
+        0..=3 => return None,
+        4..=7 => 1,
+        _ => 2,
+    };
+    let min_len = query.len() - max_typos;
+    if lower.len() < min_len {
+        return None;
+    }
+
+    // Shared by every word start, so a candidate allocates at most once
+    let mut distances = Vec::new();
+    let mut best: Option<(usize, usize, usize)> = None;
+    for start in (0..=lower.len() - min_len).filter(|&j| j == 0 || boundary_bonus(original, j) > 0)
+    {
+        let word = &lower[start..];
+        // Within `max_typos` edits one of the first query characters survives
+        // near the start of the word
+        let head = &word[..word.len().min(max_typos * 2 + 1)];
+        if !query[..=max_typos].iter().any(|c| head.contains(c)) {
+            continue;
+        }
+
+        let (distance, len) = prefix_edit_distance(query, word, &mut distances);
+        if distance <= max_typos
+            && best.is_none_or(|(best_distance, _, _)| distance < best_distance)
+        {
+            best = Some((distance, start, len));
+        }
+    }
+
+    let (distance, start, len) = best?;
+    Some(single_range(
+        TYPO_SCORE - TYPO_PENALTY * distance as f32,
+        start,
+        len,
+    ))
+}
+
+/// Smallest edit distance (with transpositions) between `query` and any
+/// prefix of `text`, along with the length of that prefix. `distances` is
+/// scratch space for the table, reused between calls.
+fn prefix_edit_distance(
+    query: &[char],
+    text: &[char],
+    distances: &mut Vec<usize>,
+) -> (usize, usize) {
+    let text = &text[..text.len().min(query.len() * 2)];
+    let (n, m) = (query.len(), text.len());
+    // distances[i * width + j]: edit distance between query[..i] and text[..j]
+    let width = m + 1;
+    distances.clear();
+    distances.resize((n + 1) * width, 0);
+
+    for i in 0..=n {
+        distances[i * width] = i;
+    }
+    for (j, distance) in distances[..width].iter_mut().enumerate() {
+        *distance = j;
+    }
+
+    for i in 1..=n {
+        for j in 1..=m {
+            let substitution = usize::from(query[i - 1] != text[j - 1]);
+            let mut distance = (distances[(i - 1) * width + j] + 1)
+                .min(distances[i * width + j - 1] + 1)
+                .min(distances[(i - 1) * width + j - 1] + substitution);
+            if i > 1 && j > 1 && query[i - 1] == text[j - 2] && query[i - 2] == text[j - 1] {
+                distance = distance.min(distances[(i - 2) * width + j - 2] + 1);
+            }
+            distances[i * width + j] = distance;
+        }
+    }
+
+    (1..=m)
+        .map(|j| (distances[n * width + j], j))
+        .min()
+        .unwrap_or((n, 0))
+}
+
+fn is_subsequence(query: &[char], text: &[char]) -> bool {
+    let mut remaining = query.iter().peekable();
+    for c in text {
+        if remaining.peek() == Some(&c) {
+            remaining.next();
+        }
+    }
+    remaining.peek().is_none()
+}
+
+fn boundary_bonus(original: &[char], index: usize) -> i32 {
+    let current = original[index];
+    let Some(&before) = index.checked_sub(1).and_then(|i| original.get(i)) else {
+        return BOUNDARY_BONUS;
+    };
+
+    if !before.is_alphanumeric() && current.is_alphanumeric() {
+        BOUNDARY_BONUS
+    } else if (before.is_lowercase() && current.is_uppercase())
+        || (!before.is_ascii_digit() && current.is_ascii_digit())
+    {
+        CAMEL_CASE_BONUS
+    } else {
+        0
+    }
+}
+
+fn merge_positions(positions: &[usize]) -> Vec<MatchRange> {
+    let mut ranges: Vec<MatchRange> = Vec::new();
+    for &position in positions {
+        match ranges.last_mut() {
+            Some(range) if range.end == position => range.end += 1,
+            _ => ranges.push(MatchRange {
+                start: position,
+                end: position + 1,
+            }),
+        }
+    }
+    ranges
+}
+
+// Lowercases one character at a time so indices stay aligned with the original
+fn to_lower(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges(found: &FuzzyMatch) -> Vec<(usize, usize)> {
+        found.ranges.iter().map(|r| (r.start, r.end)).collect()
+    }
+
+    #[test]
+    fn ranks_exact_above_prefix_above_substring() {
+        let exact = fuzzy_match("code", "Code").unwrap();
+        let prefix = fuzzy_match("code", "Code - OSS").unwrap();
+        let word = fuzzy_match("code", "Visual Studio Code").unwrap();
+        assert!(exact.score > prefix.score && prefix.score > word.score);
+        assert_eq!(ranges(&word), [(14, 18)]);
+    }
+
+    #[test]
+    fn matches_word_starts_as_subsequence() {
+        let found = fuzzy_match("vscd", "Visual Studio Code").unwrap();
+        assert!(found.score >= SUBSEQUENCE_MIN_SCORE && found.score <= SUBSEQUENCE_MAX_SCORE);
+        assert_eq!(ranges(&found), [(0, 1), (7, 8), (14, 15), (16, 17)]);
+    }
+
+    #[test]
+    fn tolerates_typos() {
+        // A missing letter still leaves a subsequence
+        let found = fuzzy_match("firfox", "Firefox Web Browser").unwrap();
+        assert!(found.score > TYPO_SCORE);
+        assert_eq!(ranges(&found), [(0, 3), (4, 7)]);
+
+        let found = fuzzy_match("fierfox", "Firefox Web Browser").unwrap();
+        assert_eq!(found.score, TYPO_SCORE - TYPO_PENALTY);
+        assert_eq!(ranges(&found), [(0, 7)]);
+
+        let found = fuzzy_match("borwser", "Firefox Web Browser").unwrap();
+        assert_eq!(ranges(&found), [(12, 19)]);
+    }
+
+    #[test]
+    fn rejects_unrelated_candidates() {
+        assert!(fuzzy_match("firfox", "Thunderbird").is_none());
+        assert!(fuzzy_match("xyz", "Firefox").is_none());
+        assert!(fuzzy_match("firefox", "fox").is_none());
+        assert!(fuzzy_match("  ", "Firefox").is_none());
+    }
+
+    #[test]
+    fn reuses_the_distance_table() {
+        let mut distances = Vec::new();
+        let query: Vec<char> = "firfox".chars().collect();
+        let firefox: Vec<char> = "firefox".chars().collect();
+        let fox: Vec<char> = "fox".chars().collect();
+        assert_eq!(
+            prefix_edit_distance(&query, &firefox, &mut distances),
+            (1, 7)
+        );
+        assert_eq!(prefix_edit_distance(&query, &fox, &mut distances), (3, 3));
+    }
+}
@@ -0,0 +1,467 @@
+use crate::config::{read_config, write_atomically};
+use crate::models::{IndexState, IndexStatus, SearchDirectory};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, UNIX_EPOCH};
+use tauri::{command, AppHandle, Emitter};
+
+use super::rules::{DirectoryRules, IgnoreStack};
+
+// Bump whenever the on-disk layout changes so stale indexes are rebuilt
+const INDEX_VERSION: u32 = 4;
+const INDEX_FILE_NAME: &str = "file_index.json";
+const REFRESH_INTERVAL: Duration = Duration::from_secs(300);
+
+// Global in-memory index, swapped atomically after each refresh
+static FILE_INDEX: OnceLock<RwLock<Arc<FileIndex>>> = OnceLock::new();
+
+// Set while a full refresh is walking the search roots
+static INDEXING: AtomicBool = AtomicBool::new(false);
+
+// Directories the watcher changed while a full refresh was running; they are
+// rescanned in the rebuilt index so those changes aren't lost in the swap
+static CHANGED_DURING_REFRESH: Mutex<Option<HashSet<PathBuf>>> = Mutex::new(None);
+
+// Wakes the background indexer before its next scheduled refresh
+static REFRESH_REQUESTS: OnceLock<Sender<()>> = OnceLock::new();
+
+fn get_file_index() -> &'static RwLock<Arc<FileIndex>> {
+    FILE_INDEX.get_or_init(|| RwLock::new(Arc::new(load_index().unwrap_or_default())))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub name: String,
+    pub path: String,
+    pub is_dir: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedDirectory {
+    // Directory mtime in nanoseconds, used to skip unchanged directories on refresh
+    modified: u64,
+    // Latest mtime of the directory's own ignore files, 0 when it has none
+    ignore_modified: u64,
+    // Position of the owning search directory in `FileIndex::roots`, and distance from it
+    root: usize,
+    depth: usize,
+    // Searchable children, and the subdirectories to descend into
+    entries: Vec<IndexEntry>,
+    subdirectories: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FileIndex {
+    version: u32,
+    // Configuration the index was built from; a change invalidates every cached directory
+    roots: Vec<SearchDirectory>,
+    global_excludes: Vec<String>,
+    #[serde(skip)]
+    rules: Vec<Arc<DirectoryRules>>,
+    directories: HashMap<String, IndexedDirectory>,
+}
+
+impl FileIndex {
+    pub fn entries(&self) -> impl Iterator<Item = &IndexEntry> {
+        self.directories
+            .values()
+            .flat_map(|directory| directory.entries.iter())
+    }
+
+    pub fn len(&self) -> usize {
+        self.directories
+            .values()
+            .map(|directory| directory.entries.len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Paths of every directory whose children are indexed.
+    pub fn directories(&self) -> impl Iterator<Item = &str> {
+        self.directories.keys().map(String::as_str)
+    }
+
+    /// Walks the given search directories, only re-reading directories whose
+    /// mtime changed since `previous` was built.
+    fn build(
+        roots: Vec<SearchDirectory>,
+        global_excludes: Vec<String>,
+        previous: &FileIndex,
+    ) -> FileIndex {
+        let empty = FileIndex::default();
+        let previous = if previous.roots == roots && previous.global_excludes == global_excludes {
+            previous
+        } else {
+            &empty
+        };
+
+        let mut index = FileIndex {
+            version: INDEX_VERSION,
+            rules: compile_rules(&roots, &global_excludes),
+            roots,
+            global_excludes,
+            directories: HashMap::new(),
+        };
+        let pending = index
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(root, rules)| PendingDirectory {
+                path: rules.root.clone(),
+                root,
+                depth: 0,
+                ignores: IgnoreStack::default(),
+                force_rescan: false,
+            })
+            .collect();
+
+        index.walk(pending, previous);
+        index
+    }
+
+    fn walk(&mut self, mut pending: Vec<PendingDirectory>, previous: &FileIndex) {
+        while let Some(next) = pending.pop() {
+            let PendingDirectory {
+                path: dir,
+                root,
+                depth,
+                ignores,
+                force_rescan,
+            } = next;
+
+            let rules = Arc::clone(&self.rules[root]);
+            if depth >= rules.max_depth {
+                continue;
+            }
+            let Some(key) = dir.to_str().map(str::to_string) else {
+                continue;
+            };
+            if self.directories.contains_key(&key) {
+                continue;
+            }
+            let Some(modified) = modified_time(&dir) else {
+                continue;
+            };
+
+            let (ignores, ignore_modified) = ignores.child(&dir);
+            let cached = previous.directories.get(&key);
+            // Edited ignore files change what every directory below should contain
+            let force_rescan = force_rescan
+                || cached.is_some_and(|cached| cached.ignore_modified != ignore_modified);
+
+            let directory = match cached {
+                Some(cached) if !force_rescan && cached.modified == modified => cached.clone(),
+                _ => {
+                    let scan = DirectoryScan {
+                        modified,
+                        ignore_modified,
+                        root,
+                        depth,
+                    };
+                    match scan_directory(&dir, scan, &rules, &ignores) {
+                        Some(scanned) => scanned,
+                        None => continue,
+                    }
+                }
+            };
+
+            for subdirectory in &directory.subdirectories {
+                pending.push(PendingDirectory {
+                    path: PathBuf::from(subdirectory),
+                    root,
+                    depth: depth + 1,
+                    ignores: ignores.clone(),
+                    force_rescan,
+                });
+            }
+            self.directories.insert(key, directory);
+        }
+    }
+
+    /// Re-reads a single indexed directory, indexing new subdirectories and
+    /// dropping the subtrees of removed ones.
+    fn rescan(&mut self, dir: &Path) {
+        let Some(key) = dir.to_str() else {
+            return;
+        };
+        let Some(existing) = self.directories.get(key) else {
+            return;
+        };
+        let (root, depth) = (existing.root, existing.depth);
+        let old_ignore_modified = existing.ignore_modified;
+        let old_subdirs: HashSet<String> = existing.subdirectories.iter().cloned().collect();
+
+        let rules = Arc::clone(&self.rules[root]);
+        let (ignores, ignore_modified) = IgnoreStack::for_directory(&rules.root, dir);
+        let scanned = modified_time(dir).and_then(|modified| {
+            let scan = DirectoryScan {
+                modified,
+                ignore_modified,
+                root,
+                depth,
+            };
+            scan_directory(dir, scan, &rules, &ignores)
+        });
+        let Some(scanned) = scanned else {
+            self.remove_tree(key);
+            return;
+        };
+
+        // When the ignore files changed, the whole subtree has to be re-walked
+        let ignores_changed = ignore_modified != old_ignore_modified;
+        let new_subdirs: HashSet<String> = scanned.subdirectories.iter().cloned().collect();
+        for removed in old_subdirs
+            .iter()
+            .filter(|subdir| ignores_changed || !new_subdirs.contains(*subdir))
+        {
+            self.remove_tree(removed);
+        }
+        let pending = new_subdirs
+            .iter()
+            .filter(|subdir| ignores_changed || !old_subdirs.contains(*subdir))
+            .map(|added| PendingDirectory {
+                path: PathBuf::from(added),
+                root,
+                depth: depth + 1,
+                ignores: ignores.clone(),
+                force_rescan: true,
+            })
+            .collect();
+
+        self.directories.insert(key.to_string(), scanned);
+        self.walk(pending, &FileIndex::default());
+    }
+
+    fn remove_tree(&mut self, dir: &str) {
+        let prefix = format!("{}/", dir.trim_end_matches('/'));
+        self.directories
+            .retain(|path, _| path != dir && !path.starts_with(&prefix));
+    }
+}
+
+struct PendingDirectory {
+    path: PathBuf,
+    root: usize,
+    depth: usize,
+    // Ignore files of the parent directories
+    ignores: IgnoreStack,
+    force_rescan: bool,
+}
+
+struct DirectoryScan {
+    modified: u64,
+    ignore_modified: u64,
+    root: usize,
+    depth: usize,
+}
+
+fn compile_rules(
+    roots: &[SearchDirectory],
+    global_excludes: &[String],
+) -> Vec<Arc<DirectoryRules>> {
+    roots
+        .iter()
+        .map(|directory| Arc::new(DirectoryRules::new(directory, global_excludes)))
+        .collect()
+}
+
+/// Returns the current index without holding the lock during queries.
+pub fn snapshot() -> Arc<FileIndex> {
+    get_file_index()
+        .read()
+        .map(|index| Arc::clone(&index))
+        .unwrap_or_default()
+}
+
+#[command]
+pub async fn get_index_status() -> Result<IndexStatus, String> {
+    Ok(current_status())
+}
+
+pub fn current_status() -> IndexStatus {
+    let state = if INDEXING.load(Ordering::SeqCst) {
+        IndexState::Indexing
+    } else {
+        IndexState::Ready
+    };
+
+    IndexStatus {
+        state,
+        indexed_entries: snapshot().len(),
+    }
+}
+
+pub fn emit_status(handle: &AppHandle) {
+    if let Err(e) = handle.emit("file_index_status", current_status()) {
+        eprintln!("Error emitting file index status: {}", e);
+    }
+}
+
+/// Applies filesystem changes reported by the watcher to the in-memory index.
+pub fn update_directories(changed: &[PathBuf]) {
+    if let Ok(mut current) = get_file_index().write() {
+        let index = Arc::make_mut(&mut current);
+        for dir in changed {
+            index.rescan(dir);
+        }
+
+        // Recorded under the index lock, so a refresh either sees it or runs after it
+        if let Ok(mut recording) = CHANGED_DURING_REFRESH.lock() {
+            if let Some(recorded) = recording.as_mut() {
+                recorded.extend(changed.iter().cloned());
+            }
+        }
+    }
+}
+
+/// Writes the current in-memory index to disk.
+pub fn persist_index() -> Result<(), String> {
+    save_index(&snapshot())
+}
+
+/// Asks the background indexer to refresh now, e.g. after the configuration changed.
+pub fn request_refresh() {
+    if let Some(requests) = REFRESH_REQUESTS.get() {
+        requests.send(()).ok();
+    }
+}
+
+/// Rebuilds the index incrementally from the configured search directories
+/// and persists it to disk.
+pub fn refresh_index() -> Result<usize, String> {
+    let config = read_config().unwrap_or_default();
+    *CHANGED_DURING_REFRESH.lock().map_err(|e| e.to_string())? = Some(HashSet::new());
+
+    let previous = snapshot();
+    let mut index = FileIndex::build(config.search_directories, config.global_excludes, &previous);
+
+    {
+        let mut current = get_file_index().write().map_err(|e| e.to_string())?;
+        let changed = CHANGED_DURING_REFRESH
+            .lock()
+            .map_err(|e| e.to_string())?
+            .take()
+            .unwrap_or_default();
+        for dir in &changed {
+            index.rescan(dir);
+        }
+        *current = Arc::new(index);
+    }
+
+    let index = snapshot();
+    save_index(&index)?;
+    Ok(index.len())
+}
+
+/// Loads the cached index and keeps it fresh from a background thread.
+pub fn start_background_indexing(handle: AppHandle) {
+    let (tx, rx) = mpsc::channel();
+    if REFRESH_REQUESTS.set(tx).is_err() {
+        return;
+    }
+
+    std::thread::spawn(move || loop {
+        INDEXING.store(true, Ordering::SeqCst);
+        emit_status(&handle);
+
+        // The entry count reaches the frontend through the status event
+        if let Err(e) = refresh_index() {
+            eprintln!("Error refreshing file index: {}", e);
+        }
+
+        INDEXING.store(false, Ordering::SeqCst);
+        emit_status(&handle);
+
+        // Sleep until the next scheduled refresh unless one is requested sooner
+        if let Err(mpsc::RecvTimeoutError::Disconnected) = rx.recv_timeout(REFRESH_INTERVAL) {
+            break;
+        }
+        while rx.try_recv().is_ok() {}
+    });
+}
+
+fn scan_directory(
+    dir: &Path,
+    scan: DirectoryScan,
+    rules: &DirectoryRules,
+    ignores: &IgnoreStack,
+) -> Option<IndexedDirectory> {
+    let read_dir = std::fs::read_dir(dir).ok()?;
+    let relative_dir = dir.strip_prefix(&rules.root).unwrap_or(Path::new(""));
+
+    let mut entries = Vec::new();
+    let mut subdirectories = Vec::new();
+
+    for entry in read_dir.filter_map(|entry| entry.ok()) {
+        let (file_name, path) = (entry.file_name(), entry.path());
+        let (Some(name), Some(path)) = (file_name.to_str(), path.to_str()) else {
+            continue;
+        };
+        let relative_path = relative_dir.join(name);
+        let relative_path = relative_path.to_str().unwrap_or(name);
+        if rules.is_excluded(name, relative_path) {
+            continue;
+        }
+
+        // file_type() does not follow symlinks, so linked directories are not descended into
+        let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+        if ignores.is_ignored(Path::new(path), is_dir) {
+            continue;
+        }
+        if is_dir {
+            subdirectories.push(path.to_string());
+        }
+        if rules.is_searchable(name, relative_path) {
+            entries.push(IndexEntry {
+                name: name.to_string(),
+                path: path.to_string(),
+                is_dir,
+            });
+        }
+    }
+
+    Some(IndexedDirectory {
+        modified: scan.modified,
+        ignore_modified: scan.ignore_modified,
+        root: scan.root,
+        depth: scan.depth,
+        entries,
+        subdirectories,
+    })
+}
+
+pub(super) fn modified_time(path: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_nanos() as u64)
+}
+
+fn get_index_path() -> Result<PathBuf, String> {
+    Ok(dirs::cache_dir()
+        .ok_or("Could not find cache directory")?
+        .join("lumina")
+        .join(INDEX_FILE_NAME))
+}
+
+fn load_index() -> Result<FileIndex, String> {
+    let index_content = std::fs::read_to_string(get_index_path()?).map_err(|e| e.to_string())?;
+    let mut index: FileIndex = serde_json::from_str(&index_content).map_err(|e| e.to_string())?;
+
+    if index.version != INDEX_VERSION {
+        return Err("File index version mismatch".to_string());
+    }
+    index.rules = compile_rules(&index.roots, &index.global_excludes);
+
+    Ok(index)
+}
+
+fn save_index(index: &FileIndex) -> Result<(), String> {
+    let index_json = serde_json::to_string(index).map_err(|e| e.to_string())?;
+    write_atomically(&get_index_path()?, index_json)
+}
@@ -1,48 +1,117 @@
-use crate::models::SearchResult;
-use tauri::command;
+use crate::config::load_config;
+use crate::models::{SearchResult, SearchUpdate};
+use std::sync::{Mutex, OnceLock};
+use tauri::async_runtime::JoinHandle;
+use tauri::{command, Emitter, Window};
 
 pub mod ai_search;
+pub mod answers;
+pub mod applications;
 pub mod calculator;
+pub mod currency;
+pub mod datetime;
+pub mod encoding;
 pub mod files;
+pub mod fuzzy;
+pub mod index;
+pub mod provider;
+pub mod rules;
+pub mod shell;
+pub mod units;
+pub mod watcher;
 
 pub use ai_search::{create_ai_search_result, is_ai_query};
+pub use applications::search_applications;
 pub use calculator::calculate;
 pub use files::search_files;
 
-#[command]
-pub async fn search(query: String) -> Result<Vec<SearchResult>, String> {
-    let mut results = Vec::new();
+use provider::ProviderRegistry;
 
-    if query.trim().is_empty() {
-        return Ok(results);
-    }
+const MAX_RESULTS: usize = 10;
 
-    // File search
-    if let Ok(file_results) = search_files(&query).await {
-        results.extend(file_results);
-    }
+// Every result source; add new providers here
+static PROVIDERS: OnceLock<ProviderRegistry> = OnceLock::new();
+// The search whose results are currently being streamed to the frontend
+static CURRENT_SEARCH: Mutex<Option<RunningSearch>> = Mutex::new(None);
+
+struct RunningSearch {
+    generation: u64,
+    task: JoinHandle<()>,
+}
+
+fn get_providers() -> &'static ProviderRegistry {
+    PROVIDERS.get_or_init(|| {
+        let mut registry = ProviderRegistry::default();
+        registry.register(applications::ApplicationsProvider);
+        registry.register(files::FilesProvider);
+        registry.register(calculator::CalculatorProvider);
+        registry.register(units::UnitsProvider);
+        registry.register(currency::CurrencyProvider);
+        registry.register(datetime::DateTimeProvider);
+        registry.register(encoding::EncodingProvider);
+        registry.register(ai_search::AiProvider);
+        registry.register(answers::AnswersProvider);
+        registry.register(shell::ShellProvider);
+        registry
+    })
+}
 
-    // Calculator
-    if let Ok(calc_result) = calculate(&query).await {
-        results.push(calc_result);
+/// Starts a search whose results are emitted as `search_results` events as
+/// providers finish. Any search still running for an older query is
+/// cancelled, so only the newest generation produces events.
+#[command]
+pub fn start_search(query: String, generation: u64, window: Window) -> Result<(), String> {
+    let mut current = CURRENT_SEARCH.lock().map_err(|e| e.to_string())?;
+    if let Some(previous) = current.take() {
+        previous.task.abort();
     }
 
-    // AI Integration - if no specific results found or query seems like a question
-    if results.is_empty() || is_ai_query(&query) {
-        if let Ok(ai_result) = create_ai_search_result(&query).await {
-            results.push(ai_result);
+    let task = tauri::async_runtime::spawn(async move {
+        let emit = |results: Vec<SearchResult>, done: bool| {
+            let update = SearchUpdate {
+                generation,
+                results,
+                done,
+            };
+            if let Err(e) = window.emit("search_results", update) {
+                eprintln!("Failed to emit search results: {}", e);
+            }
+        };
+
+        let config = load_config().await.unwrap_or_default();
+        if query.trim().is_empty() {
+            let hint = get_providers().prefix_hint(&config.keyword_prefixes);
+            emit(hint.into_iter().collect(), true);
+            return;
         }
-    }
 
-    // Sort by score (highest first)
-    results.sort_by(|a, b| {
-        b.score
-            .partial_cmp(&a.score)
-            .unwrap_or(std::cmp::Ordering::Equal)
+        let provider_results = get_providers()
+            .stream(&query, &config, |partial| {
+                emit(provider::rank(&query, partial, MAX_RESULTS), false)
+            })
+            .await;
+        emit(provider::rank(&query, &provider_results, MAX_RESULTS), true);
+
+        if let Ok(mut current) = CURRENT_SEARCH.lock() {
+            if current
+                .as_ref()
+                .is_some_and(|search| search.generation == generation)
+            {
+                current.take();
+            }
+        }
     });
 
-    // Limit to top 10 results
-    results.truncate(10);
+    *current = Some(RunningSearch { generation, task });
+    Ok(())
+}
 
-    Ok(results)
+/// Cancels the running search, e.g. because the query was cleared.
+#[command]
+pub fn cancel_search() {
+    if let Ok(mut current) = CURRENT_SEARCH.lock() {
+        if let Some(search) = current.take() {
+            search.task.abort();
+        }
+    }
 }
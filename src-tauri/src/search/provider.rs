@@ -0,0 +1,347 @@
+use crate::models::{ActionType, Config, KeywordPrefix, SearchResult};
+use async_trait::async_trait;
+use futures::stream::{FuturesUnordered, StreamExt};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::AbortHandle;
+
+// Providers that don't set their own timeout are abandoned after this long
+const DEFAULT_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// A source of search results, such as installed applications or the calculator.
+#[async_trait]
+pub trait SearchProvider: Send + Sync {
+    /// Short identifier used in logs and by keyword prefixes in the config
+    fn name(&self) -> &'static str;
+
+    /// What the provider searches, shown when listing keyword prefixes
+    fn title(&self) -> &'static str;
+
+    /// Breaks ties between equally scored results; higher wins.
+    fn priority(&self) -> i32 {
+        0
+    }
+
+    fn timeout(&self) -> Duration {
+        DEFAULT_TIMEOUT
+    }
+
+    /// Whether the provider wants to see this query at all.
+    fn handles(&self, _query: &str, _config: &Config) -> bool {
+        true
+    }
+
+    /// Fallback providers also run when no other provider found anything,
+    /// even if they don't handle the query.
+    fn is_fallback(&self, _config: &Config) -> bool {
+        false
+    }
+
+    /// Finds results for the query. Providers that take a while check
+    /// `cancellation` as they go and give up once it is set.
+    async fn query(
+        &self,
+        query: &str,
+        cancellation: &Cancellation,
+    ) -> Result<Vec<SearchResult>, String>;
+}
+
+/// Set once nobody waits for a provider's results anymore, because the search
+/// was superseded or the provider timed out. Blocking work can't be aborted,
+/// so providers poll this instead.
+#[derive(Clone, Default)]
+pub struct Cancellation(Arc<AtomicBool>);
+
+impl Cancellation {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Results of one provider, with the provider's priority for ranking.
+pub struct ProviderResults {
+    pub priority: i32,
+    pub results: Vec<SearchResult>,
+}
+
+#[derive(Default)]
+pub struct ProviderRegistry {
+    providers: Vec<Arc<dyn SearchProvider>>,
+}
+
+impl ProviderRegistry {
+    pub fn register(&mut self, provider: impl SearchProvider + 'static) {
+        self.providers.push(Arc::new(provider));
+    }
+
+    /// Runs every provider that applies to the query concurrently, calling
+    /// `on_results` with everything collected so far each time a provider
+    /// finishes with results. A provider that fails or exceeds its timeout
+    /// contributes no results. Dropping the returned future cancels the
+    /// providers that are still running.
+    pub async fn stream(
+        &self,
+        query: &str,
+        config: &Config,
+        mut on_results: impl FnMut(&[ProviderResults]),
+    ) -> Vec<ProviderResults> {
+        let mut all_results = Vec::new();
+
+        // A keyword prefix selects a single provider
+        let triggered = config.keyword_prefixes.iter().find_map(|keyword| {
+            if keyword.prefix.trim().is_empty() {
+                return None;
+            }
+            let stripped = query.strip_prefix(keyword.prefix.as_str())?;
+            let provider = self.find(&keyword.provider)?;
+            Some((provider, stripped.to_string()))
+        });
+        if let Some((provider, stripped)) = triggered {
+            run_providers(
+                vec![(provider, stripped)],
+                &mut all_results,
+                &mut on_results,
+            )
+            .await;
+            return all_results;
+        }
+
+        let (regular, fallbacks): (Vec<_>, Vec<_>) = self
+            .providers
+            .iter()
+            .cloned()
+            .partition(|provider| !provider.is_fallback(config));
+
+        let selected = regular
+            .into_iter()
+            .chain(fallbacks.iter().cloned())
+            .filter(|provider| provider.handles(query, config))
+            .map(|provider| (provider, query.to_string()))
+            .collect();
+        run_providers(selected, &mut all_results, &mut on_results).await;
+
+        if all_results.iter().all(|results| results.results.is_empty()) {
+            let unhandled = fallbacks
+                .into_iter()
+                .filter(|provider| !provider.handles(query, config))
+                .map(|provider| (provider, query.to_string()))
+                .collect();
+            run_providers(unhandled, &mut all_results, &mut on_results).await;
+        }
+
+        all_results
+    }
+
+    fn find(&self, name: &str) -> Option<Arc<dyn SearchProvider>> {
+        self.providers
+            .iter()
+            .find(|provider| provider.name() == name)
+            .cloned()
+    }
+
+    /// Result listing the keyword prefixes, offered while the query is empty.
+    pub fn prefix_hint(&self, prefixes: &[KeywordPrefix]) -> Option<SearchResult> {
+        let descriptions: Vec<String> = prefixes
+            .iter()
+            .filter(|keyword| !keyword.prefix.trim().is_empty())
+            .filter_map(|keyword| {
+                let provider = self.find(&keyword.provider)?;
+                Some(format!(
+                    "\"{}\" {}",
+                    keyword.prefix.trim(),
+                    provider.title()
+                ))
+            })
+            .collect();
+        if descriptions.is_empty() {
+            return None;
+        }
+
+        Some(SearchResult {
+            id: "keyword_hint".to_string(),
+            title: "Start with a keyword to search one source".to_string(),
+            description: descriptions.join(" · "),
+            icon: Some("⌨️".to_string()),
+            action_type: ActionType::Hint,
+            action_data: String::new(),
+            score: 0.0,
+            matched_ranges: Vec::new(),
+            secondary_actions: Vec::new(),
+            action_label: None,
+        })
+    }
+}
+
+// Cancels the provider tasks when the search is dropped; tasks that haven't
+// started yet are aborted outright
+struct ProviderTasks(Vec<(AbortHandle, Cancellation)>);
+
+impl Drop for ProviderTasks {
+    fn drop(&mut self) {
+        for (task, cancellation) in &self.0 {
+            cancellation.cancel();
+            task.abort();
+        }
+    }
+}
+
+async fn run_providers(
+    providers: Vec<(Arc<dyn SearchProvider>, String)>,
+    all_results: &mut Vec<ProviderResults>,
+    on_results: &mut impl FnMut(&[ProviderResults]),
+) {
+    let mut tasks = ProviderTasks(Vec::new());
+    let mut pending = FuturesUnordered::new();
+
+    for (provider, query) in providers {
+        let priority = provider.priority();
+        let name = provider.name();
+        let timeout = provider.timeout();
+
+        // Providers match, walk and read files synchronously, so they run on
+        // blocking threads where they can't stall the runtime, and a timeout
+        // at least frees the search from waiting for them
+        let runtime = tokio::runtime::Handle::current();
+        let cancellation = Cancellation::default();
+        let task = {
+            let cancellation = cancellation.clone();
+            tokio::task::spawn_blocking(move || {
+                runtime.block_on(provider.query(&query, &cancellation))
+            })
+        };
+        tasks.0.push((task.abort_handle(), cancellation.clone()));
+
+        pending.push(async move {
+            let results = match tokio::time::timeout(timeout, task).await {
+                Ok(Ok(Ok(results))) => results,
+                Ok(Ok(Err(_))) => Vec::new(),
+                Ok(Err(e)) => {
+                    eprintln!("Search provider {} failed: {}", name, e);
+                    Vec::new()
+                }
+                Err(_) => {
+                    cancellation.cancel();
+                    eprintln!("Search provider {} timed out", name);
+                    Vec::new()
+                }
+            };
+            ProviderResults { priority, results }
+        });
+    }
+
+    while let Some(provider_results) = pending.next().await {
+        let has_results = !provider_results.results.is_empty();
+        all_results.push(provider_results);
+        if has_results {
+            on_results(all_results);
+        }
+    }
+}
+
+/// Merges the results of all providers into a single ranked list.
+pub fn rank(query: &str, provider_results: &[ProviderResults], limit: usize) -> Vec<SearchResult> {
+    let mut ranked: Vec<(i32, SearchResult)> = provider_results
+        .iter()
+        .flat_map(|provider| {
+            provider
+                .results
+                .iter()
+                .map(|result| (provider.priority, result.clone()))
+        })
+        .collect();
+
+    // Favor results that were picked before
+    crate::history::apply_frecency(query, ranked.iter_mut().map(|(_, result)| result));
+
+    // Sort by score (highest first), then by provider priority
+    ranked.sort_by(|(a_priority, a), (b_priority, b)| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(b_priority.cmp(a_priority))
+    });
+    ranked.truncate(limit);
+
+    ranked.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    // Spins until cancelled, then records that it stopped
+    struct Spinning {
+        timeout: Duration,
+        stopped: Arc<AtomicBool>,
+    }
+
+    #[async_trait]
+    impl SearchProvider for Spinning {
+        fn name(&self) -> &'static str {
+            "spinning"
+        }
+
+        fn title(&self) -> &'static str {
+            "Spinning"
+        }
+
+        fn timeout(&self) -> Duration {
+            self.timeout
+        }
+
+        async fn query(
+            &self,
+            _query: &str,
+            cancellation: &Cancellation,
+        ) -> Result<Vec<SearchResult>, String> {
+            while !cancellation.is_cancelled() {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            self.stopped.store(true, Ordering::Relaxed);
+            Ok(Vec::new())
+        }
+    }
+
+    fn registry(timeout: Duration) -> (ProviderRegistry, Arc<AtomicBool>) {
+        let stopped = Arc::new(AtomicBool::new(false));
+        let mut registry = ProviderRegistry::default();
+        registry.register(Spinning {
+            timeout,
+            stopped: stopped.clone(),
+        });
+        (registry, stopped)
+    }
+
+    async fn wait_until_stopped(stopped: &AtomicBool) {
+        let start = Instant::now();
+        while !stopped.load(Ordering::Relaxed) {
+            assert!(start.elapsed() < Duration::from_secs(2), "still running");
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn cancels_providers_that_time_out() {
+        let (registry, stopped) = registry(Duration::from_millis(50));
+        let results = registry.stream("query", &Config::default(), |_| {}).await;
+        assert!(results.iter().all(|results| results.results.is_empty()));
+        wait_until_stopped(&stopped).await;
+    }
+
+    #[tokio::test]
+    async fn cancels_providers_of_dropped_searches() {
+        let (registry, stopped) = registry(Duration::from_secs(60));
+        let config = Config::default();
+        let search = registry.stream("query", &config, |_| {});
+        assert!(tokio::time::timeout(Duration::from_millis(50), search)
+            .await
+            .is_err());
+        wait_until_stopped(&stopped).await;
+    }
+}
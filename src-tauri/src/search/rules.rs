@@ -0,0 +1,144 @@
+use crate::models::SearchDirectory;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::index::modified_time;
+
+/// Per-directory ignore files, in increasing order of precedence.
+pub const IGNORE_FILE_NAMES: [&str; 3] = [".gitignore", ".ignore", ".luminaignore"];
+
+/// Compiled traversal rules for one configured search directory.
+#[derive(Debug, Clone)]
+pub struct DirectoryRules {
+    pub root: PathBuf,
+    pub max_depth: usize,
+    include: GlobSet,
+    has_include: bool,
+    exclude: GlobSet,
+    include_hidden: bool,
+}
+
+impl DirectoryRules {
+    pub fn new(directory: &SearchDirectory, global_excludes: &[String]) -> Self {
+        let excludes: Vec<String> = global_excludes
+            .iter()
+            .chain(&directory.exclude)
+            .cloned()
+            .collect();
+
+        Self {
+            root: directory.expanded_path(),
+            max_depth: directory.max_depth,
+            include: build_glob_set(&directory.include),
+            has_include: !directory.include.is_empty(),
+            exclude: build_glob_set(&excludes),
+            include_hidden: directory.include_hidden,
+        }
+    }
+
+    /// Whether an entry is skipped entirely, neither searchable nor descended into.
+    /// `relative_path` is relative to the search directory.
+    pub fn is_excluded(&self, name: &str, relative_path: &str) -> bool {
+        if !self.include_hidden && name.starts_with('.') {
+            return true;
+        }
+        matches_glob(&self.exclude, name, relative_path)
+    }
+
+    /// Whether a non-excluded entry should show up in search results.
+    /// Directories are still descended into when they don't match.
+    pub fn is_searchable(&self, name: &str, relative_path: &str) -> bool {
+        !self.has_include || matches_glob(&self.include, name, relative_path)
+    }
+}
+
+/// Ignore files collected from the search root down to a directory.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreStack {
+    // Outermost directory first
+    matchers: Vec<Arc<Gitignore>>,
+}
+
+impl IgnoreStack {
+    /// Builds the stack for `dir` by reading ignore files from `root` downwards.
+    pub fn for_directory(root: &Path, dir: &Path) -> (IgnoreStack, u64) {
+        let mut stack = IgnoreStack::default();
+        let mut stamp = 0;
+
+        if let Ok(relative) = dir.strip_prefix(root) {
+            let mut current = root.to_path_buf();
+            (stack, _) = stack.child(&current);
+            for component in relative.components() {
+                current.push(component);
+                (stack, stamp) = stack.child(&current);
+            }
+        } else {
+            (stack, stamp) = stack.child(dir);
+        }
+
+        (stack, stamp)
+    }
+
+    /// Extends the stack with the ignore files found in `dir`, returning the
+    /// new stack and a stamp that changes whenever those files are modified.
+    pub fn child(&self, dir: &Path) -> (IgnoreStack, u64) {
+        let mut builder = GitignoreBuilder::new(dir);
+        let mut stamp = 0;
+
+        for file_name in IGNORE_FILE_NAMES {
+            let path = dir.join(file_name);
+            let Some(modified) = modified_time(&path) else {
+                continue;
+            };
+            stamp = stamp.max(modified);
+            if let Some(e) = builder.add(&path) {
+                eprintln!("Error reading ignore file '{}': {}", path.display(), e);
+            }
+        }
+
+        if stamp == 0 {
+            return (self.clone(), stamp);
+        }
+
+        let mut stack = self.clone();
+        match builder.build() {
+            Ok(matcher) => stack.matchers.push(Arc::new(matcher)),
+            Err(e) => eprintln!("Error parsing ignore files in '{}': {}", dir.display(), e),
+        }
+        (stack, stamp)
+    }
+
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        // The innermost ignore file with an opinion wins, and it may re-include with `!`
+        for matcher in self.matchers.iter().rev() {
+            let matched = matcher.matched(path, is_dir);
+            if matched.is_ignore() {
+                return true;
+            }
+            if matched.is_whitelist() {
+                return false;
+            }
+        }
+        false
+    }
+}
+
+// Patterns without a slash match the file name, like in .gitignore
+fn matches_glob(set: &GlobSet, name: &str, relative_path: &str) -> bool {
+    set.is_match(name) || set.is_match(relative_path)
+}
+
+fn build_glob_set(patterns: &[String]) -> GlobSet {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        match Glob::new(pattern) {
+            Ok(glob) => {
+                builder.add(glob);
+            }
+            Err(e) => eprintln!("Invalid search pattern '{}': {}", pattern, e),
+        }
+    }
+    builder.build().unwrap_or_else(|_| GlobSet::empty())
+}
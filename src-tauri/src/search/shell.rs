@@ -0,0 +1,51 @@
+use crate::models::{ActionType, Config, SearchResult, SecondaryAction};
+
+use super::provider::{Cancellation, SearchProvider};
+
+/// Runs the query as a shell command. Only reachable through its keyword
+/// prefix, since any text could be a command.
+pub struct ShellProvider;
+
+#[async_trait::async_trait]
+impl SearchProvider for ShellProvider {
+    fn name(&self) -> &'static str {
+        "shell"
+    }
+
+    fn title(&self) -> &'static str {
+        "Shell command"
+    }
+
+    fn handles(&self, _query: &str, _config: &Config) -> bool {
+        false
+    }
+
+    async fn query(
+        &self,
+        query: &str,
+        _cancellation: &Cancellation,
+    ) -> Result<Vec<SearchResult>, String> {
+        let command = query.trim();
+        if command.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        Ok(vec![SearchResult {
+            id: "shell_command".to_string(),
+            title: command.to_string(),
+            description: "Run in the background".to_string(),
+            icon: Some("💻".to_string()),
+            action_type: ActionType::RunCommand,
+            action_data: command.to_string(),
+            score: 1.0,
+            matched_ranges: Vec::new(),
+            secondary_actions: vec![SecondaryAction {
+                id: "run_in_terminal".to_string(),
+                title: "Run in terminal".to_string(),
+                action_type: ActionType::RunInTerminal,
+                action_data: command.to_string(),
+            }],
+            action_label: Some("Run Command".to_string()),
+        }])
+    }
+}
@@ -0,0 +1,621 @@
+use crate::models::{ActionType, SearchResult};
+use bigdecimal::BigDecimal;
+use std::str::FromStr;
+
+use super::calculator::{bounded, format_number, rfind_ignore_ascii_case};
+use super::provider::{Cancellation, SearchProvider};
+
+// Significant digits kept in converted values
+const SIGNIFICANT_DIGITS: u64 = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Category {
+    Length,
+    Mass,
+    Temperature,
+    Volume,
+    DataSize,
+    Time,
+    Speed,
+    Area,
+}
+
+impl Category {
+    fn name(self) -> &'static str {
+        match self {
+            Category::Length => "Length",
+            Category::Mass => "Mass",
+            Category::Temperature => "Temperature",
+            Category::Volume => "Volume",
+            Category::DataSize => "Data size",
+            Category::Time => "Time",
+            Category::Speed => "Speed",
+            Category::Area => "Area",
+        }
+    }
+}
+
+/// A unit converts to its category's base unit as
+/// `(value + offset) * numerator / denominator`.
+struct Unit {
+    category: Category,
+    /// The first name is the symbol shown in results
+    names: &'static [&'static str],
+    numerator: &'static str,
+    denominator: &'static str,
+    offset: &'static str,
+}
+
+const fn unit(category: Category, factor: &'static str, names: &'static [&'static str]) -> Unit {
+    Unit {
+        category,
+        names,
+        numerator: factor,
+        denominator: "1",
+        offset: "0",
+    }
+}
+
+const fn ratio(
+    category: Category,
+    numerator: &'static str,
+    denominator: &'static str,
+    names: &'static [&'static str],
+) -> Unit {
+    Unit {
+        category,
+        names,
+        numerator,
+        denominator,
+        offset: "0",
+    }
+}
+
+// Units are matched case-sensitively first, so when names only differ in
+// case (MB and Mb) the exact spelling wins; otherwise the earlier unit does.
+const UNITS: &[Unit] = &[
+    // Length, in meters
+    unit(
+        Category::Length,
+        "1",
+        &["m", "meter", "meters", "metre", "metres"],
+    ),
+    unit(
+        Category::Length,
+        "1000",
+        &["km", "kilometer", "kilometers", "kilometre", "kilometres"],
+    ),
+    unit(
+        Category::Length,
+        "0.01",
+        &[
+            "cm",
+            "centimeter",
+            "centimeters",
+            "centimetre",
+            "centimetres",
+        ],
+    ),
+    unit(
+        Category::Length,
+        "0.001",
+        &[
+            "mm",
+            "millimeter",
+            "millimeters",
+            "millimetre",
+            "millimetres",
+        ],
+    ),
+    unit(
+        Category::Length,
+        "0.000001",
+        &["µm", "um", "micrometer", "micrometers", "micron", "microns"],
+    ),
+    unit(
+        Category::Length,
+        "0.000000001",
+        &["nm", "nanometer", "nanometers"],
+    ),
+    unit(Category::Length, "1609.344", &["mi", "mile", "miles"]),
+    unit(Category::Length, "0.9144", &["yd", "yard", "yards"]),
+    unit(Category::Length, "0.3048", &["ft", "foot", "feet", "'"]),
+    unit(Category::Length, "0.0254", &["in", "inch", "inches", "\""]),
+    unit(
+        Category::Length,
+        "1852",
+        &["nmi", "nautical mile", "nautical miles"],
+    ),
+    // Mass, in kilograms
+    unit(
+        Category::Mass,
+        "1",
+        &["kg", "kilogram", "kilograms", "kilo", "kilos"],
+    ),
+    unit(Category::Mass, "0.001", &["g", "gram", "grams"]),
+    unit(
+        Category::Mass,
+        "0.000001",
+        &["mg", "milligram", "milligrams"],
+    ),
+    unit(
+        Category::Mass,
+        "0.000000001",
+        &["µg", "ug", "microgram", "micrograms"],
+    ),
+    unit(
+        Category::Mass,
+        "1000",
+        &["t", "tonne", "tonnes", "metric ton", "metric tons"],
+    ),
+    unit(
+        Category::Mass,
+        "0.45359237",
+        &["lb", "lbs", "pound", "pounds"],
+    ),
+    unit(Category::Mass, "0.028349523125", &["oz", "ounce", "ounces"]),
+    unit(Category::Mass, "6.35029318", &["st", "stone", "stones"]),
+    // Temperature, in kelvin
+    unit(Category::Temperature, "1", &["K", "kelvin"]),
+    Unit {
+        category: Category::Temperature,
+        names: &["°C", "C", "celsius", "degc"],
+        numerator: "1",
+        denominator: "1",
+        offset: "273.15",
+    },
+    Unit {
+        category: Category::Temperature,
+        names: &["°F", "F", "fahrenheit", "degf"],
+        numerator: "5",
+        denominator: "9",
+        offset: "459.67",
+    },
+    // Volume, in liters
+    unit(
+        Category::Volume,
+        "1",
+        &["l", "L", "liter", "liters", "litre", "litres"],
+    ),
+    unit(
+        Category::Volume,
+        "0.001",
+        &[
+            "ml",
+            "mL",
+            "milliliter",
+            "milliliters",
+            "millilitre",
+            "millilitres",
+        ],
+    ),
+    unit(
+        Category::Volume,
+        "0.01",
+        &["cl", "cL", "centiliter", "centiliters"],
+    ),
+    unit(
+        Category::Volume,
+        "0.1",
+        &["dl", "dL", "deciliter", "deciliters"],
+    ),
+    unit(
+        Category::Volume,
+        "1000",
+        &["m³", "m3", "cubic meter", "cubic meters"],
+    ),
+    unit(
+        Category::Volume,
+        "0.001",
+        &["cm³", "cm3", "cc", "cubic centimeter", "cubic centimeters"],
+    ),
+    unit(
+        Category::Volume,
+        "3.785411784",
+        &["gal", "gallon", "gallons", "us gallon", "us gallons"],
+    ),
+    unit(
+        Category::Volume,
+        "4.54609",
+        &["imp gal", "imperial gallon", "imperial gallons"],
+    ),
+    unit(Category::Volume, "0.946352946", &["qt", "quart", "quarts"]),
+    unit(Category::Volume, "0.473176473", &["pt", "pint", "pints"]),
+    unit(Category::Volume, "0.2365882365", &["cup", "cups"]),
+    unit(
+        Category::Volume,
+        "0.0295735295625",
+        &["fl oz", "floz", "fluid ounce", "fluid ounces"],
+    ),
+    unit(
+        Category::Volume,
+        "0.01478676478125",
+        &["tbsp", "tablespoon", "tablespoons"],
+    ),
+    unit(
+        Category::Volume,
+        "0.00492892159375",
+        &["tsp", "teaspoon", "teaspoons"],
+    ),
+    // Data size, in bytes
+    unit(Category::DataSize, "1", &["B", "byte", "bytes"]),
+    unit(Category::DataSize, "0.125", &["bit", "bits", "b"]),
+    unit(
+        Category::DataSize,
+        "1000",
+        &["kB", "KB", "kilobyte", "kilobytes"],
+    ),
+    unit(
+        Category::DataSize,
+        "1000000",
+        &["MB", "megabyte", "megabytes"],
+    ),
+    unit(
+        Category::DataSize,
+        "1000000000",
+        &["GB", "gigabyte", "gigabytes"],
+    ),
+    unit(
+        Category::DataSize,
+        "1000000000000",
+        &["TB", "terabyte", "terabytes"],
+    ),
+    unit(
+        Category::DataSize,
+        "1000000000000000",
+        &["PB", "petabyte", "petabytes"],
+    ),
+    unit(
+        Category::DataSize,
+        "1024",
+        &["KiB", "kibibyte", "kibibytes"],
+    ),
+    unit(
+        Category::DataSize,
+        "1048576",
+        &["MiB", "mebibyte", "mebibytes"],
+    ),
+    unit(
+        Category::DataSize,
+        "1073741824",
+        &["GiB", "gibibyte", "gibibytes"],
+    ),
+    unit(
+        Category::DataSize,
+        "1099511627776",
+        &["TiB", "tebibyte", "tebibytes"],
+    ),
+    unit(
+        Category::DataSize,
+        "1125899906842624",
+        &["PiB", "pebibyte", "pebibytes"],
+    ),
+    unit(
+        Category::DataSize,
+        "125",
+        &["kbit", "Kb", "kilobit", "kilobits"],
+    ),
+    unit(
+        Category::DataSize,
+        "125000",
+        &["Mbit", "Mb", "megabit", "megabits"],
+    ),
+    unit(
+        Category::DataSize,
+        "125000000",
+        &["Gbit", "Gb", "gigabit", "gigabits"],
+    ),
+    // Time, in seconds
+    unit(
+        Category::Time,
+        "1",
+        &["s", "sec", "secs", "second", "seconds"],
+    ),
+    unit(
+        Category::Time,
+        "0.001",
+        &["ms", "millisecond", "milliseconds"],
+    ),
+    unit(
+        Category::Time,
+        "0.000001",
+        &["µs", "us", "microsecond", "microseconds"],
+    ),
+    unit(
+        Category::Time,
+        "0.000000001",
+        &["ns", "nanosecond", "nanoseconds"],
+    ),
+    unit(Category::Time, "60", &["min", "mins", "minute", "minutes"]),
+    unit(Category::Time, "3600", &["h", "hr", "hrs", "hour", "hours"]),
+    unit(Category::Time, "86400", &["d", "day", "days"]),
+    unit(Category::Time, "604800", &["wk", "week", "weeks"]),
+    // Average Gregorian month and year
+    unit(Category::Time, "2629746", &["mo", "month", "months"]),
+    unit(Category::Time, "31556952", &["yr", "year", "years"]),
+    // Speed, in meters per second
+    unit(Category::Speed, "1", &["m/s", "mps", "meters per second"]),
+    ratio(
+        Category::Speed,
+        "1000",
+        "3600",
+        &["km/h", "kph", "kmh", "kilometers per hour"],
+    ),
+    ratio(
+        Category::Speed,
+        "1609.344",
+        "3600",
+        &["mph", "mi/h", "miles per hour"],
+    ),
+    ratio(
+        Category::Speed,
+        "1852",
+        "3600",
+        &["kn", "kt", "knot", "knots"],
+    ),
+    unit(
+        Category::Speed,
+        "0.3048",
+        &["ft/s", "fps", "feet per second"],
+    ),
+    // Area, in square meters
+    unit(
+        Category::Area,
+        "1",
+        &["m²", "m2", "sq m", "square meter", "square meters"],
+    ),
+    unit(
+        Category::Area,
+        "1000000",
+        &[
+            "km²",
+            "km2",
+            "sq km",
+            "square kilometer",
+            "square kilometers",
+        ],
+    ),
+    unit(
+        Category::Area,
+        "0.0001",
+        &[
+            "cm²",
+            "cm2",
+            "sq cm",
+            "square centimeter",
+            "square centimeters",
+        ],
+    ),
+    unit(
+        Category::Area,
+        "0.000001",
+        &[
+            "mm²",
+            "mm2",
+            "sq mm",
+            "square millimeter",
+            "square millimeters",
+        ],
+    ),
+    unit(Category::Area, "10000", &["ha", "hectare", "hectares"]),
+    unit(Category::Area, "4046.8564224", &["ac", "acre", "acres"]),
+    unit(
+        Category::Area,
+        "0.09290304",
+        &["ft²", "ft2", "sq ft", "square foot", "square feet"],
+    ),
+    unit(
+        Category::Area,
+        "0.00064516",
+        &["in²", "in2", "sq in", "square inch", "square inches"],
+    ),
+    unit(
+        Category::Area,
+        "0.83612736",
+        &["yd²", "yd2", "sq yd", "square yard", "square yards"],
+    ),
+    unit(
+        Category::Area,
+        "2589988.110336",
+        &["mi²", "mi2", "sq mi", "square mile", "square miles"],
+    ),
+];
+
+pub struct UnitsProvider;
+
+#[async_trait::async_trait]
+impl SearchProvider for UnitsProvider {
+    fn name(&self) -> &'static str {
+        "units"
+    }
+
+    fn title(&self) -> &'static str {
+        "Unit conversion"
+    }
+
+    fn priority(&self) -> i32 {
+        35
+    }
+
+    async fn query(
+        &self,
+        query: &str,
+        _cancellation: &Cancellation,
+    ) -> Result<Vec<SearchResult>, String> {
+        convert_units(query).map(|result| vec![result])
+    }
+}
+
+/// Converts queries like "5 km in miles" or "72F to C".
+pub fn convert_units(query: &str) -> Result<SearchResult, String> {
+    let (value, from, to) = parse_conversion(query).ok_or("Not a unit conversion")?;
+    if from.category != to.category {
+        return Err(format!(
+            "Cannot convert {} to {}",
+            from.category.name().to_lowercase(),
+            to.category.name().to_lowercase()
+        ));
+    }
+
+    let converted = convert(&value, from, to)?.with_prec(SIGNIFICANT_DIGITS);
+    let formatted = format_number(&converted);
+
+    Ok(SearchResult {
+        id: "unit_conversion".to_string(),
+        title: format!(
+            "{} {} = {} {}",
+            format_number(&value),
+            from.names[0],
+            formatted,
+            to.names[0]
+        ),
+        description: format!(
+            "{} · Press Enter to copy result to clipboard",
+            from.category.name()
+        ),
+        icon: Some("📏".to_string()),
+        action_type: ActionType::CopyToClipboard,
+        action_data: formatted,
+        score: 0.9,
+        matched_ranges: Vec::new(),
+        secondary_actions: Vec::new(),
+        action_label: None,
+    })
+}
+
+fn parse_conversion(query: &str) -> Option<(BigDecimal, &'static Unit, &'static Unit)> {
+    let (value, from, to) = split_conversion(query)?;
+    Some((value, find_unit(from)?, find_unit(to)?))
+}
+
+/// Splits "<number> <unit> (in|to|as|=|->) <unit>" into the value and the two
+/// unit names. The space before the first unit is optional, as in "72F".
+pub(super) fn split_conversion(query: &str) -> Option<(BigDecimal, &str, &str)> {
+    let query = query.trim();
+
+    let (left, right) = [" in ", " to ", " as ", " = ", " -> ", " → "]
+        .iter()
+        .filter_map(|separator| {
+            let position = rfind_ignore_ascii_case(query, separator)?;
+            Some((&query[..position], &query[position + separator.len()..]))
+        })
+        .max_by_key(|(left, _)| left.len())?;
+
+    let number_end = left
+        .char_indices()
+        .find(|&(i, c)| {
+            let exponent = (c == 'e' || c == 'E')
+                && left[i + 1..].starts_with(|next: char| next.is_ascii_digit() || next == '-');
+            !(c.is_ascii_digit() || matches!(c, '.' | '-' | '+' | '_' | ',') || exponent)
+        })
+        .map_or(left.len(), |(i, _)| i);
+    if !has_valid_thousands_separators(&left[..number_end]) {
+        return None;
+    }
+    let number: String = left[..number_end]
+        .chars()
+        .filter(|c| !matches!(c, '_' | ','))
+        .collect();
+    let value = bounded(BigDecimal::from_str(&number).ok()?).ok()?;
+
+    Some((value, left[number_end..].trim(), right.trim()))
+}
+
+// Commas only separate groups of three digits, so that "1,5" (a decimal comma)
+// isn't read as 15
+fn has_valid_thousands_separators(number: &str) -> bool {
+    if !number.contains(',') {
+        return true;
+    }
+    let unsigned = number.trim_start_matches(['-', '+']);
+    let integer_end = unsigned.find(['.', 'e', 'E']).unwrap_or(unsigned.len());
+    let (integer, rest) = unsigned.split_at(integer_end);
+    if rest.contains(',') {
+        return false;
+    }
+
+    let mut groups = integer.split(',');
+    let leading = groups.next().unwrap_or_default();
+    (1..=3).contains(&leading.len())
+        && leading.chars().all(|c| c.is_ascii_digit())
+        && groups.all(|group| group.len() == 3 && group.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn find_unit(name: &str) -> Option<&'static Unit> {
+    let name = name
+        .trim_start_matches("degrees ")
+        .trim_start_matches("degree ");
+    UNITS
+        .iter()
+        .find(|unit| unit.names.contains(&name))
+        .or_else(|| {
+            UNITS
+                .iter()
+                .find(|unit| unit.names.iter().any(|n| n.eq_ignore_ascii_case(name)))
+        })
+}
+
+fn convert(value: &BigDecimal, from: &Unit, to: &Unit) -> Result<BigDecimal, String> {
+    let decimal = |s: &str| BigDecimal::from_str(s).map_err(|e| e.to_string());
+
+    let base =
+        (value + decimal(from.offset)?) * decimal(from.numerator)? / decimal(from.denominator)?;
+    Ok(base * decimal(to.denominator)? / decimal(to.numerator)? - decimal(to.offset)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn converted(query: &str) -> String {
+        convert_units(query).unwrap().action_data
+    }
+
+    #[test]
+    fn converts_example_queries() {
+        assert_eq!(converted("5 km in miles"), "3.10685596119");
+        assert_eq!(converted("72F to C"), "22.2222222222");
+        assert_eq!(converted("3.5 GiB in MB"), "3758.096384");
+        assert_eq!(converted("90 min to h"), "1.5");
+    }
+
+    #[test]
+    fn prefers_exact_case_for_units() {
+        assert_eq!(converted("1 MB in Mb"), "8");
+        assert_eq!(converted("8 Mb in MB"), "1");
+        // Without an exact match the first unit of that spelling wins
+        assert_eq!(converted("1 mb in kB"), "1000");
+    }
+
+    #[test]
+    fn rejects_units_of_different_categories() {
+        assert_eq!(
+            convert_units("5 km in kg").unwrap_err(),
+            "Cannot convert length to mass"
+        );
+    }
+
+    #[test]
+    fn handles_any_case_and_script() {
+        assert_eq!(converted("5 KM IN M"), "5000");
+        assert!(convert_units("İİİ to x").is_err());
+        assert!(convert_units("ẞ to x").is_err());
+    }
+
+    #[test]
+    fn reads_commas_only_as_thousands_separators() {
+        assert_eq!(converted("1,500 m in km"), "1.5");
+        assert_eq!(converted("-12,345.5 m in km"), "-12.3455");
+        assert_eq!(converted("1,000,000 mm in km"), "1");
+        assert!(convert_units("1,5 km in m").is_err());
+        assert!(convert_units("1,5000 km in m").is_err());
+        assert!(convert_units(",500 km in m").is_err());
+        assert!(convert_units("1.500,5 km in m").is_err());
+    }
+
+    #[test]
+    fn rejects_huge_values() {
+        assert!(convert_units("1e99999999 km in mi").is_err());
+        assert!(convert_units("1e-99999999 km in mi").is_err());
+    }
+}
@@ -0,0 +1,188 @@
+use notify::event::ModifyKind;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+use super::applications::{invalidate_applications, set_watching};
+use super::index;
+use super::rules::IGNORE_FILE_NAMES;
+use crate::desktop_entry::application_directories;
+
+// How long to wait for follow-up events before applying a batch
+const DEBOUNCE_INTERVAL: Duration = Duration::from_millis(250);
+// How often watches are reconciled with the directories in the index
+const SYNC_INTERVAL: Duration = Duration::from_secs(5);
+// Minimum delay between writes of a watcher-updated index to disk
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Watches every indexed directory with inotify and applies changes to the
+/// file index as they happen.
+pub fn start_file_watcher(handle: AppHandle) {
+    std::thread::spawn(move || {
+        if let Err(e) = run_file_watcher(&handle) {
+            eprintln!("File watcher stopped: {}", e);
+        }
+    });
+}
+
+fn run_file_watcher(handle: &AppHandle) -> Result<(), String> {
+    let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
+    let mut watcher = notify::recommended_watcher(tx).map_err(|e| e.to_string())?;
+    let mut watched = HashSet::new();
+    let mut unsaved_changes = false;
+    let mut last_saved = Instant::now();
+
+    loop {
+        sync_watches(&mut watcher, &mut watched);
+
+        let mut changed = HashSet::new();
+        match rx.recv_timeout(SYNC_INTERVAL) {
+            Ok(event) => collect_changed_directories(event, &mut changed),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err("Watcher event channel closed".to_string())
+            }
+        }
+
+        // Coalesce bursts (e.g. extracting an archive) into a single update
+        let deadline = Instant::now() + DEBOUNCE_INTERVAL;
+        while let Ok(event) = rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            collect_changed_directories(event, &mut changed);
+        }
+
+        if !changed.is_empty() {
+            let changed: Vec<PathBuf> = changed.into_iter().collect();
+            index::update_directories(&changed);
+            index::emit_status(handle);
+            unsaved_changes = true;
+        }
+
+        if unsaved_changes && last_saved.elapsed() >= SAVE_INTERVAL {
+            if let Err(e) = index::persist_index() {
+                eprintln!("Error saving file index: {}", e);
+            }
+            unsaved_changes = false;
+            last_saved = Instant::now();
+        }
+    }
+}
+
+/// Watches the application directories, including subdirectories such as
+/// kde4/, and marks the installed applications as changed on any event.
+pub fn start_application_watcher() {
+    std::thread::spawn(|| {
+        if let Err(e) = run_application_watcher() {
+            eprintln!("Application watcher stopped: {}", e);
+        }
+        set_watching(false);
+    });
+}
+
+fn run_application_watcher() -> Result<(), String> {
+    let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
+    let mut watcher = notify::recommended_watcher(tx).map_err(|e| e.to_string())?;
+    let mut watched: HashSet<PathBuf> = HashSet::new();
+    set_watching(true);
+
+    loop {
+        // Directories such as the Flatpak exports may appear or vanish at any time
+        watched.retain(|dir| {
+            let exists = dir.is_dir();
+            if !exists {
+                watcher.unwatch(dir).ok();
+                invalidate_applications();
+            }
+            exists
+        });
+        for dir in application_directories() {
+            if !watched.contains(&dir)
+                && dir.is_dir()
+                && watcher.watch(&dir, RecursiveMode::Recursive).is_ok()
+            {
+                watched.insert(dir);
+                invalidate_applications();
+            }
+        }
+
+        match rx.recv_timeout(SYNC_INTERVAL) {
+            // Loading the applications opens every desktop file, which must not
+            // count as a change
+            Ok(Ok(event)) if matches!(event.kind, EventKind::Access(_)) => {}
+            Ok(Ok(_)) => invalidate_applications(),
+            Ok(Err(e)) => eprintln!("Application watcher error: {}", e),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err("Watcher event channel closed".to_string())
+            }
+        }
+    }
+}
+
+/// Adds watches for newly indexed directories and drops those that left the index.
+fn sync_watches(watcher: &mut RecommendedWatcher, watched: &mut HashSet<PathBuf>) {
+    let snapshot = index::snapshot();
+    let indexed: HashSet<PathBuf> = snapshot.directories().map(PathBuf::from).collect();
+
+    for dir in watched.difference(&indexed) {
+        // The directory may already be gone, in which case inotify dropped the watch itself
+        watcher.unwatch(dir).ok();
+    }
+    watched.retain(|dir| indexed.contains(dir));
+
+    for dir in indexed {
+        if watched.contains(&dir) {
+            continue;
+        }
+        match watcher.watch(&dir, RecursiveMode::NonRecursive) {
+            Ok(()) => {
+                watched.insert(dir);
+            }
+            Err(e) => {
+                // Most likely the inotify watch limit; retrying every sync would only spam the log
+                eprintln!("Error watching '{}': {}", dir.display(), e);
+                watched.insert(dir);
+            }
+        }
+    }
+}
+
+/// Maps an event to the indexed directories whose listings it affects.
+fn collect_changed_directories(event: notify::Result<Event>, changed: &mut HashSet<PathBuf>) {
+    let event = match event {
+        Ok(event) => event,
+        Err(e) => {
+            eprintln!("File watcher error: {}", e);
+            return;
+        }
+    };
+
+    // Content and metadata changes don't affect which files exist, except in ignore files
+    let affects_listing = matches!(
+        event.kind,
+        EventKind::Create(_)
+            | EventKind::Remove(_)
+            | EventKind::Modify(ModifyKind::Name(_))
+            | EventKind::Any
+            | EventKind::Other
+    );
+    let is_modify = matches!(event.kind, EventKind::Modify(_));
+
+    for path in &event.paths {
+        let reindex = affects_listing || (is_modify && is_ignore_file(path));
+        if !reindex {
+            continue;
+        }
+        if let Some(parent) = path.parent().map(Path::to_path_buf) {
+            changed.insert(parent);
+        }
+    }
+}
+
+fn is_ignore_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| IGNORE_FILE_NAMES.contains(&name))
+}
@@ -2,6 +2,8 @@ use std::sync::{Arc, Mutex, OnceLock};
 use tauri::{command, Manager, PhysicalPosition, PhysicalSize, UserAttentionType, Window, WebviewWindow};
 use tokio::time::{sleep, Duration};
 
+use crate::conversation::cancel_reply;
+
 // Constants for window dimensions
 pub const DEFAULT_WINDOW_WIDTH: u32 = 700;
 pub const DEFAULT_WINDOW_HEIGHT: u32 = 600;
@@ -28,6 +30,7 @@ pub async fn show_window(window: Window) {
 pub async fn hide_window(window: Window) {
     if window.is_visible().unwrap_or(false) {
         window.hide().ok();
+        cancel_reply(window.app_handle());
     }
 }
 
@@ -35,6 +38,7 @@ pub async fn hide_window(window: Window) {
 pub async fn toggle_window(window: Window) {
     if window.is_visible().unwrap_or(false) {
         window.hide().ok();
+        cancel_reply(window.app_handle());
     } else {
         position_window_center(&window);
         setup_window_display(&window).await;
@@ -126,12 +130,14 @@ fn handle_window_unfocus(handle: &tauri::AppHandle) {
     if should_hide {
         if let Some(main_window) = handle.get_webview_window("main") {
             if main_window.is_visible().unwrap_or(false) {
+                let handle = handle.clone();
                 // Add a delay to see if focus returns quickly (e.g. for dropdowns, popups)
                 std::thread::spawn(move || {
                     std::thread::sleep(std::time::Duration::from_millis(150));
                     // Only hide if still not focused after the delay
                     if !main_window.is_focused().unwrap_or(true) {
                         main_window.hide().ok();
+                        cancel_reply(&handle);
                     }
                 });
             }
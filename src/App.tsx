@@ -6,19 +6,28 @@ import SearchBar from "./components/SearchBar";
 import SearchResultItem from "./components/SearchResultItem";
 import AiResponseDisplay from "./components/AiResponseDisplay";
 import SettingsComponent from "./components/SettingsComponent";
-import { SearchResult, Config } from "./types";
+import { SearchResult, SearchUpdate, Config } from "./types";
 
 function App() {
   const [query, setQuery] = useState("");
   const [results, setResults] = useState<SearchResult[]>([]);
   const [selectedIndex, setSelectedIndex] = useState(0);
+  // Index into the selected result's secondary actions while that menu is open
+  const [actionMenuIndex, setActionMenuIndex] = useState<number | null>(null);
   const [isLoading, setIsLoading] = useState(false);
   const [aiResponse, setAiResponse] = useState("");
   const [isAiStreaming, setIsAiStreaming] = useState(false);
+  // Conversation that follow-up questions are sent to
+  const [conversationId, setConversationId] = useState<string | null>(null);
+  // Mirrors conversationId for event listeners, updated before any request
+  const activeConversation = useRef<string | null>(null);
   const [showSettings, setShowSettings] = useState(false);
   const [config, setConfig] = useState<Config | null>(null);
   const inputRef = useRef<HTMLInputElement>(null);
   const [showContent, setShowContent] = useState(false);
+  // Number of the latest search, and of the search whose results are shown
+  const searchGeneration = useRef(0);
+  const shownGeneration = useRef(0);
 
   // Load configuration on startup
   useEffect(() => {
@@ -55,6 +64,14 @@ function App() {
       await listen("ai_response_complete", () => {
         setIsAiStreaming(false);
       });
+
+      // Replies are cancelled when a new request starts or the window hides;
+      // only a cancelled reply of the shown conversation stops the indicator
+      await listen<string>("ai_response_cancelled", (event) => {
+        if (event.payload === activeConversation.current) {
+          setIsAiStreaming(false);
+        }
+      });
     };
 
     setupListeners();
@@ -75,44 +92,58 @@ function App() {
     };
   }, []);
 
+  // Results stream in per query; events for older generations are ignored
+  useEffect(() => {
+    const unlistenPromise = listen<SearchUpdate>("search_results", (event) => {
+      const update = event.payload;
+      if (update.generation !== searchGeneration.current) {
+        return;
+      }
+
+      setResults(update.results);
+      if (update.results.length > 0) {
+        setShowContent(true);
+      }
+      // Keep the selection while later providers add results
+      if (shownGeneration.current !== update.generation) {
+        shownGeneration.current = update.generation;
+        setSelectedIndex(0);
+        setActionMenuIndex(null);
+      }
+      if (update.done) {
+        setIsLoading(false);
+      }
+    });
+    return () => {
+      unlistenPromise.then((unlisten) => unlisten());
+    };
+  }, []);
+
   // Search when query changes
   useEffect(() => {
     const searchTimeout = setTimeout(async () => {
+      const generation = ++searchGeneration.current;
+
       if (query.trim()) {
         setIsLoading(true);
         setShowContent(true);
-
-        // Minimum loading time to prevent flickering
-        const searchStartTime = Date.now();
-        const minLoadingTime = 200;
-
-        try {
-          const searchResults: SearchResult[] = await invoke("search", {
-            query,
-          });
-
-          // Ensure minimum loading time has passed
-          const elapsedTime = Date.now() - searchStartTime;
-          const remainingTime = Math.max(0, minLoadingTime - elapsedTime);
-
-          setTimeout(() => {
-            setResults(searchResults);
-            setSelectedIndex(0);
-            setIsLoading(false);
-          }, remainingTime);
-        } catch (error) {
-          console.error("Search failed:", error);
-          setResults([]);
-          setIsLoading(false);
-        }
       } else {
+        // The backend answers an empty query with the keyword prefix hint
         setResults([]);
         setSelectedIndex(0);
         if (!aiResponse && !isAiStreaming) {
           setShowContent(false);
         }
       }
-    }, 500); // Increased delay from 300ms to 500ms for better UX
+
+      try {
+        await invoke("start_search", { query, generation });
+      } catch (error) {
+        console.error("Search failed:", error);
+        setResults([]);
+        setIsLoading(false);
+      }
+    }, 100); // Short delay, stale searches are cancelled by the backend
 
     return () => clearTimeout(searchTimeout);
   }, [query, aiResponse, isAiStreaming]);
@@ -134,8 +165,45 @@ function App() {
   // Handle keyboard shortcuts
   useEffect(() => {
     const handleKeyDown = async (event: KeyboardEvent) => {
+      const selectedResult = results[selectedIndex];
+      const secondaryActions = selectedResult?.secondary_actions ?? [];
+
+      if (event.key === "Tab") {
+        event.preventDefault();
+        if (actionMenuIndex === null && secondaryActions.length > 0) {
+          setActionMenuIndex(0);
+        } else {
+          setActionMenuIndex(null);
+        }
+        return;
+      }
+
+      if (actionMenuIndex !== null) {
+        if (event.key === "Escape") {
+          event.preventDefault();
+          setActionMenuIndex(null);
+        } else if (event.key === "ArrowDown") {
+          event.preventDefault();
+          setActionMenuIndex(
+            Math.min(actionMenuIndex + 1, secondaryActions.length - 1)
+          );
+        } else if (event.key === "ArrowUp") {
+          event.preventDefault();
+          setActionMenuIndex(Math.max(actionMenuIndex - 1, 0));
+        } else if (event.key === "Enter") {
+          event.preventDefault();
+          const action = secondaryActions[actionMenuIndex];
+          if (selectedResult && action) {
+            await executeSecondaryAction(selectedResult, action.id);
+          }
+        }
+        return;
+      }
+
       if (event.key === "Escape") {
         try {
+          await invoke("cancel_ai_request");
+          resetConversation();
           setQuery("");
           setResults([]);
           setAiResponse("");
@@ -156,7 +224,13 @@ function App() {
         setSelectedIndex((prev) => Math.max(prev - 1, 0));
       } else if (event.key === "Enter") {
         event.preventDefault();
-        if (results.length > 0 && selectedIndex < results.length) {
+        const followUpId =
+          aiResponse && !isAiStreaming && query.trim() ? conversationId : null;
+        // Results typed after an answer stay reachable with Enter; Ctrl+Enter
+        // always asks, and Enter asks when nothing matched
+        if (followUpId && (event.ctrlKey || results.length === 0)) {
+          await continueConversation(followUpId, query);
+        } else if (results.length > 0 && selectedIndex < results.length) {
           await executeAction(results[selectedIndex]);
         }
       } else if (event.ctrlKey && event.key === ",") {
@@ -167,7 +241,15 @@ function App() {
 
     document.addEventListener("keydown", handleKeyDown);
     return () => document.removeEventListener("keydown", handleKeyDown);
-  }, [results, selectedIndex]);
+  }, [
+    results,
+    selectedIndex,
+    actionMenuIndex,
+    conversationId,
+    aiResponse,
+    isAiStreaming,
+    query,
+  ]);
 
   useEffect(() => {
     const handleSettingsResize = async () => {
@@ -190,8 +272,16 @@ function App() {
   }, [showSettings, showContent]);
 
   const executeAction = async (result: SearchResult) => {
+    if (result.action_type === "Hint") {
+      return;
+    }
+
     try {
-      if (result.action_type === "AiResponse") {
+      const opensConversation =
+        result.action_type === "AiResponse" ||
+        result.action_type === "ReopenConversation";
+      if (opensConversation) {
+        resetConversation();
         setAiResponse("");
         setIsAiStreaming(true);
         setShowContent(true);
@@ -199,10 +289,16 @@ function App() {
         setQuery("");
       }
 
-      const response = await invoke("execute_action", { result });
+      const response = await invoke<string>("execute_action", {
+        result,
+        query,
+      });
       console.log("Action executed:", response);
 
-      if (result.action_type !== "AiResponse") {
+      if (opensConversation) {
+        // The response is the id of the conversation
+        showConversation(response);
+      } else {
         setQuery("");
         setResults([]);
         setShowContent(false);
@@ -213,7 +309,77 @@ function App() {
     }
   };
 
+  const executeSecondaryAction = async (
+    result: SearchResult,
+    actionId: string
+  ) => {
+    const action = result.secondary_actions.find((a) => a.id === actionId);
+    if (action?.action_type === "DeleteAiExchange") {
+      await deleteAnswers(action.action_data.split(","));
+      return;
+    }
+
+    try {
+      const response = await invoke("execute_secondary_action", {
+        result,
+        actionId,
+        query,
+      });
+      console.log("Secondary action executed:", response);
+
+      setActionMenuIndex(null);
+      setQuery("");
+      setResults([]);
+      setShowContent(false);
+    } catch (error) {
+      console.error("Failed to execute secondary action:", error);
+    }
+  };
+
+  // Deletes past answers and searches again so they disappear from the list
+  const deleteAnswers = async (ids: string[]) => {
+    try {
+      await invoke("delete_ai_exchanges", { ids });
+      setActionMenuIndex(null);
+      const generation = ++searchGeneration.current;
+      await invoke("start_search", { query, generation });
+    } catch (error) {
+      console.error("Failed to delete answers:", error);
+    }
+  };
+
+  const continueConversation = async (id: string, message: string) => {
+    setAiResponse("");
+    setIsAiStreaming(true);
+    setQuery("");
+    try {
+      await invoke("continue_conversation", { conversationId: id, message });
+    } catch (error) {
+      console.error("Follow-up question failed:", error);
+      setAiResponse("Error: Could not process AI request.");
+      setIsAiStreaming(false);
+    }
+  };
+
+  const showConversation = (id: string | null) => {
+    activeConversation.current = id;
+    setConversationId(id);
+  };
+
+  const resetConversation = () => {
+    if (conversationId) {
+      invoke("reset_conversation", { conversationId }).catch((error) =>
+        console.error("Failed to reset conversation:", error)
+      );
+      showConversation(null);
+    }
+  };
+
   const handleNewAiQuery = () => {
+    invoke("cancel_ai_request").catch((error) =>
+      console.error("Failed to cancel AI request:", error)
+    );
+    resetConversation();
     setAiResponse("");
     setIsAiStreaming(false);
     setShowContent(false);
@@ -229,7 +395,10 @@ function App() {
       result.action_type === "OpenApp"
     ) {
       invoke("open_path", { path: result.action_data });
-    } else if (result.action_type === "CopyToClipboard") {
+    } else if (
+      result.action_type === "CopyToClipboard" ||
+      result.action_type === "CopyCalculation"
+    ) {
       navigator.clipboard.writeText(result.action_data);
     }
   };
@@ -242,7 +411,11 @@ function App() {
       setAiResponse("");
       setShowContent(true);
       try {
-        await invoke("ai_request", { query: query.substring(1) });
+        resetConversation();
+        const id = await invoke<string>("start_conversation", {
+          message: query.substring(1),
+        });
+        showConversation(id);
       } catch (error) {
         console.error("AI request failed:", error);
         setAiResponse("Error: Could not process AI request.");
@@ -287,6 +460,11 @@ function App() {
           onSubmit={handleFormSubmit}
           inputRef={inputRef}
           isInitialEmptyState={!showContent && !query}
+          placeholder={
+            conversationId && aiResponse
+              ? "Search, or ask a follow-up with Ctrl+Enter"
+              : undefined
+          }
           onSettingsClick={() => setShowSettings(true)}
         />
 
@@ -317,6 +495,28 @@ function App() {
                         isSelected={index === selectedIndex}
                         onClick={() => handleResultClick(result)}
                       />
+                      {index === selectedIndex &&
+                        actionMenuIndex !== null && (
+                          <div className="mt-2 ml-14 space-y-1">
+                            {result.secondary_actions.map(
+                              (action, actionIndex) => (
+                                <div
+                                  key={action.id}
+                                  className={`px-3 py-2 rounded-xl text-sm cursor-pointer state-transition ${
+                                    actionIndex === actionMenuIndex
+                                      ? "bg-white/70 text-gray-900"
+                                      : "bg-white/30 text-gray-700"
+                                  }`}
+                                  onClick={() =>
+                                    executeSecondaryAction(result, action.id)
+                                  }
+                                >
+                                  {action.title}
+                                </div>
+                              )
+                            )}
+                          </div>
+                        )}
                     </div>
                   ))}
                 </div>
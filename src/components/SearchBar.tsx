@@ -6,6 +6,7 @@ interface SearchBarProps {
   onSubmit: (e: React.FormEvent) => void;
   inputRef: React.RefObject<HTMLInputElement>;
   isInitialEmptyState?: boolean;
+  placeholder?: string;
   onSettingsClick: () => void;
 }
 
@@ -14,6 +15,7 @@ const SearchBar: React.FC<SearchBarProps> = ({
   onQueryChange,
   onSubmit,
   inputRef,
+  placeholder = "Search anything or type '/' for AI",
   onSettingsClick,
 }) => {
   return (
@@ -26,7 +28,7 @@ const SearchBar: React.FC<SearchBarProps> = ({
               type="text"
               value={query}
               onChange={(e) => onQueryChange(e.target.value)}
-              placeholder="Search anything or type '/' for AI"
+              placeholder={placeholder}
               className="w-full glass-input text-gray-800 placeholder-gray-500 text-base font-normal py-4 px-5 pr-20 outline-none transition-all duration-300 ease-out rounded-2xl focus:shadow-lg focus:ring-2 focus:ring-blue-400/30 focus:border-blue-300/50"
               autoComplete="off"
             />
@@ -1,5 +1,5 @@
 import React from "react";
-import { SearchResult } from "../types"; // Adjusted import path
+import { SearchResult, MatchRange } from "../types"; // Adjusted import path
 
 interface SearchResultItemProps {
   result: SearchResult;
@@ -7,6 +7,34 @@ interface SearchResultItemProps {
   onClick: () => void;
 }
 
+// Ranges are character offsets, so split by code point rather than UTF-16 unit
+const highlightTitle = (title: string, ranges: MatchRange[] = []) => {
+  if (ranges.length === 0) {
+    return title;
+  }
+
+  const chars = Array.from(title);
+  const parts: React.ReactNode[] = [];
+  let position = 0;
+
+  ranges.forEach((range, index) => {
+    if (range.start > position) {
+      parts.push(chars.slice(position, range.start).join(""));
+    }
+    parts.push(
+      <span key={index} className="text-blue-700">
+        {chars.slice(range.start, range.end).join("")}
+      </span>
+    );
+    position = range.end;
+  });
+
+  if (position < chars.length) {
+    parts.push(chars.slice(position).join(""));
+  }
+  return parts;
+};
+
 const SearchResultItem: React.FC<SearchResultItemProps> = ({
   result,
   isSelected,
@@ -123,19 +151,24 @@ const SearchResultItem: React.FC<SearchResultItemProps> = ({
     >
       {result.icon && (
         <div className="w-10 h-10 flex items-center justify-center text-lg rounded-xl bg-white/40 group-hover:bg-white/60 state-transition backdrop-blur-sm border border-white/20 modern-shadow">
-          {result.icon}
+          {result.icon.startsWith("data:") ? (
+            <img src={result.icon} alt="" className="w-7 h-7 object-contain" />
+          ) : (
+            result.icon
+          )}
         </div>
       )}
       <div className="flex-1 min-w-0">
         <div className="font-semibold text-base text-gray-800 truncate group-hover:text-gray-900 state-transition-fast mb-1">
-          {result.title}
+          {highlightTitle(result.title, result.matched_ranges)}
         </div>
         <div className="text-sm text-gray-600 truncate group-hover:text-gray-700 state-transition-fast">
           {result.description}
         </div>
       </div>
       <div className="ml-3 px-3 py-2 rounded-xl bg-gradient-to-r from-white/40 to-white/30 border border-white/30 text-xs font-medium text-gray-700 group-hover:text-gray-800 group-hover:from-white/60 group-hover:to-white/50 state-transition whitespace-nowrap backdrop-blur-sm modern-shadow">
-        {result.action_type.replace(/([A-Z])/g, " $1").trim()}
+        {result.action_label ??
+          result.action_type.replace(/([A-Z])/g, " $1").trim()}
       </div>
     </div>
   );
@@ -1,6 +1,90 @@
 import React from "react";
+import { invoke } from "@tauri-apps/api/core";
 import { Config } from "../types";
 
+type ModelField =
+  | "default_model"
+  | "openai_model"
+  | "anthropic_model"
+  | "gemini_model"
+  | "ollama_model";
+
+interface AiService {
+  id: string;
+  label: string;
+  keyField?:
+    | "openrouter_api_key"
+    | "openai_api_key"
+    | "anthropic_api_key"
+    | "gemini_api_key";
+  keyUrl?: string;
+  modelField: ModelField;
+  // Suggestions, named the way the service expects
+  models: [string, string][];
+}
+
+// Built-in services; OpenAI-compatible endpoints from the config are listed after them
+const AI_SERVICES: AiService[] = [
+  {
+    id: "openrouter",
+    label: "OpenRouter",
+    keyField: "openrouter_api_key",
+    keyUrl: "https://openrouter.ai",
+    modelField: "default_model",
+    models: [
+      ["anthropic/claude-3.5-sonnet", "Claude 3.5 Sonnet"],
+      ["openai/gpt-4o", "GPT-4o"],
+      ["google/gemini-pro-1.5", "Gemini Pro 1.5"],
+      ["meta-llama/llama-3.2-90b-vision-instruct", "Llama 3.2 90B"],
+      ["google/gemma-2-9b-it:free", "Gemma 2 9B"],
+      ["deepseek/deepseek-chat-v3-0324:free", "DeepSeek Chat V3"],
+    ],
+  },
+  {
+    id: "openai",
+    label: "OpenAI",
+    keyField: "openai_api_key",
+    keyUrl: "https://platform.openai.com",
+    modelField: "openai_model",
+    models: [
+      ["gpt-4o", "GPT-4o"],
+      ["gpt-4o-mini", "GPT-4o mini"],
+    ],
+  },
+  {
+    id: "anthropic",
+    label: "Anthropic",
+    keyField: "anthropic_api_key",
+    keyUrl: "https://console.anthropic.com",
+    modelField: "anthropic_model",
+    models: [
+      ["claude-3-5-sonnet-latest", "Claude 3.5 Sonnet"],
+      ["claude-3-5-haiku-latest", "Claude 3.5 Haiku"],
+    ],
+  },
+  {
+    id: "gemini",
+    label: "Gemini",
+    keyField: "gemini_api_key",
+    keyUrl: "https://aistudio.google.com",
+    modelField: "gemini_model",
+    models: [
+      ["gemini-1.5-pro", "Gemini 1.5 Pro"],
+      ["gemini-1.5-flash", "Gemini 1.5 Flash"],
+    ],
+  },
+  {
+    id: "ollama",
+    label: "Ollama",
+    modelField: "ollama_model",
+    models: [
+      ["llama3.2", "Llama 3.2"],
+      ["mistral", "Mistral"],
+      ["qwen2.5", "Qwen 2.5"],
+    ],
+  },
+];
+
 interface SettingsComponentProps {
   config: Config;
   onConfigChange: (config: Config) => void;
@@ -14,6 +98,28 @@ const SettingsComponent: React.FC<SettingsComponentProps> = ({
   onSave,
   onClose,
 }) => {
+  const service = AI_SERVICES.find(({ id }) => id === config.ai_service);
+  const provider = config.ai_providers.find(
+    ({ name }) => name === config.ai_service
+  );
+  const model = service
+    ? config[service.modelField]
+    : provider?.model || "";
+
+  // Each service keeps its own model, since they name models differently
+  const setModel = (value: string) => {
+    if (service) {
+      onConfigChange({ ...config, [service.modelField]: value });
+    } else {
+      onConfigChange({
+        ...config,
+        ai_providers: config.ai_providers.map((p) =>
+          p === provider ? { ...p, model: value } : p
+        ),
+      });
+    }
+  };
+
   return (
     <div className="fixed inset-0 flex items-center justify-center p-4 z-50">
       <div className="w-full max-w-md glass-panel-enhanced rounded-3xl overflow-hidden flex flex-col">
@@ -43,61 +149,135 @@ const SettingsComponent: React.FC<SettingsComponentProps> = ({
         {/* Content */}
         <div className="p-6 overflow-y-auto flex-grow">
           <div className="space-y-6">
-            {/* API Key Field */}
+            {/* AI Service */}
             <div className="form-group">
               <label className="block text-sm font-medium text-gray-700 mb-2">
-                OpenRouter API Key
+                AI Service
               </label>
-              <input
-                type="password"
-                value={config.openrouter_api_key || ""}
+              <select
+                value={config.ai_service}
                 onChange={(e) =>
-                  onConfigChange({
-                    ...config,
-                    openrouter_api_key: e.target.value,
-                  })
+                  onConfigChange({ ...config, ai_service: e.target.value })
                 }
-                placeholder="Enter your OpenRouter API key"
+                className="w-full glass-input text-gray-800 text-sm rounded-2xl focus:ring-2 focus:ring-blue-400/50 focus:border-blue-300/50 p-3 transition-all duration-300"
+              >
+                {AI_SERVICES.map((service) => (
+                  <option key={service.id} value={service.id}>
+                    {service.label}
+                  </option>
+                ))}
+                {config.ai_providers.map((provider) => (
+                  <option key={provider.name} value={provider.name}>
+                    {provider.name}
+                  </option>
+                ))}
+              </select>
+            </div>
+
+            {/* API Key Field */}
+            {service?.keyField && (
+              <div className="form-group">
+                <label className="block text-sm font-medium text-gray-700 mb-2">
+                  {service.label} API Key
+                </label>
+                <input
+                  type="password"
+                  value={config[service.keyField] || ""}
+                  onChange={(e) =>
+                    onConfigChange({
+                      ...config,
+                      [service.keyField!]: e.target.value,
+                    })
+                  }
+                  placeholder={`Enter your ${service.label} API key`}
+                  className="w-full glass-input text-gray-800 placeholder-gray-500 text-sm rounded-2xl focus:ring-2 focus:ring-blue-400/50 focus:border-blue-300/50 p-3 transition-all duration-300"
+                />
+                {service.keyUrl && (
+                  <p className="mt-2 text-xs text-gray-600">
+                    Get your API key from{" "}
+                    <a
+                      href={service.keyUrl}
+                      target="_blank"
+                      rel="noopener noreferrer"
+                      className="font-medium text-blue-600 hover:text-blue-700 transition-colors"
+                    >
+                      {new URL(service.keyUrl).host}
+                    </a>
+                  </p>
+                )}
+              </div>
+            )}
+
+            {/* Ollama Server */}
+            {config.ai_service === "ollama" && (
+              <div className="form-group">
+                <label className="block text-sm font-medium text-gray-700 mb-2">
+                  Ollama Server
+                </label>
+                <input
+                  type="text"
+                  value={config.ollama_url}
+                  onChange={(e) =>
+                    onConfigChange({ ...config, ollama_url: e.target.value })
+                  }
+                  placeholder="http://localhost:11434"
+                  className="w-full glass-input text-gray-800 placeholder-gray-500 text-sm rounded-2xl focus:ring-2 focus:ring-blue-400/50 focus:border-blue-300/50 p-3 transition-all duration-300"
+                />
+              </div>
+            )}
+
+            {/* Model Selection */}
+            <div className="form-group">
+              <label className="block text-sm font-medium text-gray-700 mb-2">
+                Model
+              </label>
+              <input
+                type="text"
+                list="ai-models"
+                value={model}
+                onChange={(e) => setModel(e.target.value)}
+                placeholder="Model name"
                 className="w-full glass-input text-gray-800 placeholder-gray-500 text-sm rounded-2xl focus:ring-2 focus:ring-blue-400/50 focus:border-blue-300/50 p-3 transition-all duration-300"
               />
-              <p className="mt-2 text-xs text-gray-600">
-                Get your API key from{" "}
-                <a
-                  href="https://openrouter.ai"
-                  target="_blank"
-                  rel="noopener noreferrer"
-                  className="font-medium text-blue-600 hover:text-blue-700 transition-colors"
-                >
-                  openrouter.ai
-                </a>
-              </p>
+              <datalist id="ai-models">
+                {service?.models.map(([value, label]) => (
+                  <option key={value} value={value}>
+                    {label}
+                  </option>
+                ))}
+              </datalist>
+              {!service && (
+                <p className="mt-2 text-xs text-gray-600">
+                  The endpoint and key of this service are set in the config
+                  file.
+                </p>
+              )}
             </div>
 
-            {/* Model Selection */}
+            {/* AI History */}
             <div className="form-group">
               <label className="block text-sm font-medium text-gray-700 mb-2">
-                Default Model
+                AI History
               </label>
-              <select
-                value={config.default_model}
-                onChange={(e) =>
-                  onConfigChange({ ...config, default_model: e.target.value })
-                }
-                className="w-full glass-input text-gray-800 text-sm rounded-2xl focus:ring-2 focus:ring-blue-400/50 focus:border-blue-300/50 p-3 transition-all duration-300"
+              <button
+                onClick={async () => {
+                  if (!window.confirm("Delete all saved AI answers?")) {
+                    return;
+                  }
+                  try {
+                    await invoke("clear_ai_history");
+                  } catch (error) {
+                    console.error("Failed to clear AI history:", error);
+                  }
+                }}
+                className="w-full glass-button-stable text-gray-700 hover:text-gray-900 text-sm py-3 px-4 rounded-2xl"
               >
-                <option value="anthropic/claude-3.5-sonnet">
-                  Claude 3.5 Sonnet
-                </option>
-                <option value="openai/gpt-4o">GPT-4o</option>
-                <option value="google/gemini-pro-1.5">Gemini Pro 1.5</option>
-                <option value="meta-llama/llama-3.2-90b-vision-instruct">
-                  Llama 3.2 90B
-                </option>
-                <option value="google/gemma-2-9b-it:free">Gemma 2 9B</option>
-                <option value="deepseek/deepseek-chat-v3-0324:free">
-                  DeepSeek Chat V3
-                </option>
-              </select>
+                Clear saved answers
+              </button>
+              <p className="mt-2 text-xs text-gray-600">
+                Single answers can be deleted from their actions menu (Tab)
+                when searching with "past".
+              </p>
             </div>
 
             {/* Save Button */}
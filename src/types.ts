@@ -8,15 +8,96 @@ export interface SearchResult {
     | "OpenApp"
     | "OpenUrl"
     | "CopyToClipboard"
-    | "AiResponse";
+    | "CopyCalculation"
+    | "AiResponse"
+    | "DesktopAction"
+    | "OpenWith"
+    | "RunCommand"
+    | "RunInTerminal"
+    | "Hint"
+    | "ReopenConversation"
+    | "DeleteAiExchange";
   action_data: string;
   score: number;
+  matched_ranges: MatchRange[];
+  secondary_actions: SecondaryAction[];
+  action_label?: string;
+}
+
+export interface SecondaryAction {
+  id: string;
+  title: string;
+  action_type: SearchResult["action_type"];
+  action_data: string;
+}
+
+// Payload of the "search_results" event
+export interface SearchUpdate {
+  generation: number;
+  results: SearchResult[];
+  done: boolean;
+}
+
+// Half-open range of character offsets into a result title
+export interface MatchRange {
+  start: number;
+  end: number;
 }
 
 export interface Config {
   ai_service: string;
   openrouter_api_key?: string;
   openai_api_key?: string;
+  anthropic_api_key?: string;
+  gemini_api_key?: string;
+  // Model used with OpenRouter; the other services have their own
   default_model: string;
-  search_directories: string[];
+  openai_model: string;
+  anthropic_model: string;
+  gemini_model: string;
+  ollama_model: string;
+  search_directories: SearchDirectory[];
+  global_excludes: string[];
+  terminal_emulator?: string;
+  keyword_prefixes: KeywordPrefix[];
+  ai_detection: AiDetection;
+  exchange_rates_url: string;
+  ai_context_tokens: number;
+  ollama_url: string;
+  ai_providers: AiProviderConfig[];
+}
+
+// OpenAI-compatible chat endpoint, selected by name through ai_service
+export interface AiProviderConfig {
+  name: string;
+  base_url: string;
+  api_key?: string;
+  headers: Record<string, string>;
+  model?: string;
+}
+
+// When the "Ask AI" result is offered for ordinary queries
+export interface AiDetection {
+  mode: "Auto" | "Fallback" | "Explicit";
+  languages: string[];
+  trigger_words: string[];
+}
+
+// Query prefix that sends the rest of the query to one search provider
+export interface KeywordPrefix {
+  prefix: string;
+  provider: string;
+}
+
+export interface SearchDirectory {
+  path: string;
+  max_depth: number;
+  include: string[];
+  exclude: string[];
+  include_hidden: boolean;
+}
+
+export interface IndexStatus {
+  state: "Indexing" | "Ready";
+  indexed_entries: number;
 }